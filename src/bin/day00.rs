@@ -12,9 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::time;
-use aoc_2023::{oops, oops::Oops};
-use std::io::{self, Read};
+use aoc_2023::oops::Oops;
+use aoc_2023::timing::{run_timed, Solution};
 use std::str::FromStr;
 
 #[derive(Debug)]
@@ -44,17 +43,28 @@ fn part2(puzzle: &Puzzle) -> u64 {
     *puzzle.values.iter().max().unwrap()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day00;
+
+impl Solution for Day00 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
 
-    let puzzle = time!(parse(&input)?);
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part1(puzzle))
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part2(puzzle))
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day00>()
 }
 
 #[cfg(test)]
@@ -12,61 +12,32 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::oops::Oops;
-use std::io::{self, Read};
+use aoc_2023::calibration::{calibration_value, DIGITS, DIGITS_AND_DIGIT_WORDS};
+use aoc_2023::io_util::{read_input, selected_parts};
+use aoc_2023::{oops, oops::Oops};
 use std::str::FromStr;
 
-const DIGITS: &[&str] = &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
-const DIGITS_AND_DIGIT_WORDS: &[&str] = &[
-    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "zero", "one", "two", "three", "four",
-    "five", "six", "seven", "eight", "nine",
-];
-
-fn find_left(s: &str, digits: &[&str]) -> u64 {
-    let mut haystack = s;
-    let mut matched_digit = None;
-    for (needle, digit) in digits.iter().zip(0u64..) {
-        match haystack.find(needle) {
-            Some(idx) => {
-                matched_digit = Some(digit % 10);
-                haystack = &haystack[..idx + needle.len()];
-            }
-            None => continue,
-        }
-    }
-    matched_digit.unwrap_or(0)
-}
-
-fn find_right(s: &str, digits: &[&str]) -> u64 {
-    let mut haystack = s;
-    let mut matched_digit = None;
-    for (needle, digit) in digits.iter().zip(0u64..) {
-        match haystack.rfind(needle) {
-            Some(idx) => {
-                matched_digit = Some(digit % 10);
-                haystack = &haystack[idx..];
-            }
-            None => continue,
-        }
-    }
-    matched_digit.unwrap_or(0)
-}
-
+// Each part's calibration value, if the line has any of that part's tokens.
 #[derive(Debug)]
 struct Value {
-    calibration1: u64,
-    calibration2: u64,
+    calibration1: Option<u64>,
+    calibration2: Option<u64>,
 }
 
-impl FromStr for Value {
-    type Err = Oops;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Value {
-            calibration1: find_left(s, DIGITS) * 10 + find_right(s, DIGITS),
-            calibration2: find_left(s, DIGITS_AND_DIGIT_WORDS) * 10
-                + find_right(s, DIGITS_AND_DIGIT_WORDS),
-        })
+impl Value {
+    fn new(
+        s: &str,
+        part1_tokens: &[(&str, u64)],
+        part2_tokens: &[(&str, u64)],
+    ) -> Result<Self, Oops> {
+        let value = Value {
+            calibration1: calibration_value(s, part1_tokens),
+            calibration2: calibration_value(s, part2_tokens),
+        };
+        if value.calibration1.is_none() && value.calibration2.is_none() {
+            return Err(oops!("no digits in {s:?}"));
+        }
+        Ok(value)
     }
 }
 
@@ -79,9 +50,7 @@ impl FromStr for Puzzle {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Puzzle {
-            values: s.lines().map(str::parse).collect::<Result<Vec<_>, _>>()?,
-        })
+        parse_with_tokens(s, DIGITS, DIGITS_AND_DIGIT_WORDS)
     }
 }
 
@@ -89,23 +58,65 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn part1(puzzle: &Puzzle) -> u64 {
-    puzzle.values.iter().map(|v| v.calibration1).sum()
+// Like `parse`, but with the tokens each part recognizes supplied by the caller.
+fn parse_with_tokens(
+    input: &str,
+    part1_tokens: &[(&str, u64)],
+    part2_tokens: &[(&str, u64)],
+) -> Result<Puzzle, Oops> {
+    Ok(Puzzle {
+        values: input
+            .lines()
+            .map(|line| Value::new(line, part1_tokens, part2_tokens))
+            .collect::<Result<_, _>>()?,
+    })
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
-    puzzle.values.iter().map(|v| v.calibration2).sum()
+fn sum_values(values: impl Iterator<Item = Option<u64>>) -> Result<u64, Oops> {
+    values
+        .enumerate()
+        .map(|(idx, value)| value.ok_or_else(|| oops!("no digits on line {}", idx + 1)))
+        .sum()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+    sum_values(puzzle.values.iter().map(|v| v.calibration1))
+}
+
+fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+    sum_values(puzzle.values.iter().map(|v| v.calibration2))
+}
 
-    let puzzle = parse(&input)?;
+// Sums the calibration values line by line, without building a `Puzzle`.
+fn solve_with_tokens(input: &str, tokens: &[(&str, u64)]) -> Result<u64, Oops> {
+    input
+        .lines()
+        .map(|line| calibration_value(line, tokens).ok_or_else(|| oops!("no digits in {line:?}")))
+        .sum()
+}
+
+fn solve_part1(input: &str) -> Result<u64, Oops> {
+    solve_with_tokens(input, DIGITS)
+}
+
+fn solve_part2(input: &str) -> Result<u64, Oops> {
+    solve_with_tokens(input, DIGITS_AND_DIGIT_WORDS)
+}
 
-    println!("{}", part1(&puzzle));
-    println!("{}", part2(&puzzle));
+fn main() -> Result<(), Oops> {
+    let input = read_input()?;
+    let (run_part1, run_part2) = selected_parts()?;
+
+    // When only one part is wanted, skip computing the other part's value for every line.
+    if run_part1 && run_part2 {
+        let puzzle = parse(&input)?;
+        println!("{}", part1(&puzzle)?);
+        println!("{}", part2(&puzzle)?);
+    } else if run_part1 {
+        println!("{}", solve_part1(&input)?);
+    } else if run_part2 {
+        println!("{}", solve_part2(&input)?);
+    }
 
     Ok(())
 }
@@ -127,11 +138,37 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(142, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(142, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
-        assert_eq!(281, part2(&parse(SAMPLE2).unwrap()));
+        assert_eq!(281, part2(&parse(SAMPLE2).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn streaming() {
+        assert_eq!(142, solve_part1(SAMPLE).unwrap());
+        assert_eq!(281, solve_part2(SAMPLE2).unwrap());
+    }
+
+    #[test]
+    fn custom_tokens() {
+        let puzzle = parse_with_tokens("a1b\nonetwo\n", DIGITS, &[("1", 1), ("one", 1)]).unwrap();
+        // "onetwo" has no plain digits for part 1.
+        assert!(part1(&puzzle).is_err());
+        assert_eq!(22, part2(&puzzle).unwrap());
+    }
+
+    #[test]
+    fn no_digits() {
+        let error = parse("1abc2\nnothing here\n").unwrap_err().to_string();
+        assert!(error.contains("no digits in \"nothing here\""), "{error}");
+        let error = part1(&parse("1abc2\nxtwoy\n").unwrap())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no digits on line 2"), "{error}");
+        let error = solve_part1("1abc2\nxtwoy\n").unwrap_err().to_string();
+        assert!(error.contains("no digits in \"xtwoy\""), "{error}");
     }
 }
@@ -12,48 +12,115 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
-use std::io::{self, Read};
 use std::str::FromStr;
 
-#[derive(Debug)]
-struct Game {
-    id: u64,
+// The cubes shown in a single handful.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct Reveal {
     red: u64,
     green: u64,
     blue: u64,
 }
 
+impl FromStr for Reveal {
+    type Err = Oops;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut reveal = Reveal::default();
+        for marbles in s.split(',') {
+            let marbles = marbles.trim();
+            let (count, color) = marbles
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| oops!("expected a count and a colour, got {marbles:?}"))?;
+            let count = count
+                .parse::<u64>()
+                .map_err(|_| oops!("invalid count in {marbles:?}"))?;
+            // A colour repeated within one reveal counts as its largest count.
+            let seen = match color.trim() {
+                "red" => &mut reveal.red,
+                "green" => &mut reveal.green,
+                "blue" => &mut reveal.blue,
+                color => return Err(oops!("unknown colour {color:?}")),
+            };
+            *seen = std::cmp::max(*seen, count);
+        }
+        Ok(reveal)
+    }
+}
+
+// The most cubes of each color the bag holds.
+#[derive(Clone, Copy, Debug)]
+struct Limits {
+    red: u64,
+    green: u64,
+    blue: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            red: 12,
+            green: 13,
+            blue: 14,
+        }
+    }
+}
+
+impl Limits {
+    fn allows(self, reveal: &Reveal) -> bool {
+        reveal.red <= self.red && reveal.green <= self.green && reveal.blue <= self.blue
+    }
+}
+
+#[derive(Debug)]
+struct Game {
+    id: u64,
+    reveals: Vec<Reveal>,
+}
+
+impl Game {
+    // The most cubes of each color seen in any one reveal.
+    fn maxima(&self) -> Reveal {
+        Reveal {
+            red: self
+                .reveals
+                .iter()
+                .map(|reveal| reveal.red)
+                .max()
+                .unwrap_or(0),
+            green: self
+                .reveals
+                .iter()
+                .map(|reveal| reveal.green)
+                .max()
+                .unwrap_or(0),
+            blue: self
+                .reveals
+                .iter()
+                .map(|reveal| reveal.blue)
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
 impl FromStr for Game {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (game, seen_sets) = s.split_once(": ").ok_or_else(|| oops!("malformed line"))?;
+        let (game, seen_sets) = s.split_once(':').ok_or_else(|| oops!("malformed line"))?;
         let game = game
-            .strip_prefix("Game ")
+            .trim()
+            .strip_prefix("Game")
             .ok_or_else(|| oops!("malformed game ID"))?;
-        let id = game.parse::<u64>()?;
-        seen_sets.split("; ").try_fold(
-            Game {
-                id,
-                red: 0,
-                green: 0,
-                blue: 0,
-            },
-            |mut game, seen_set| {
-                for marbles in seen_set.split(", ") {
-                    let (count, color) = marbles.split_once(' ').unwrap();
-                    let count = count.parse::<u64>()?;
-                    match color {
-                        "red" => game.red = std::cmp::max(game.red, count),
-                        "green" => game.green = std::cmp::max(game.green, count),
-                        "blue" => game.blue = std::cmp::max(game.blue, count),
-                        _ => return Err(oops!("unknown colour")),
-                    }
-                }
-                Ok(game)
-            },
-        )
+        let id = game.trim().parse::<u64>()?;
+        let reveals = seen_sets
+            .split(';')
+            .map(str::parse)
+            .collect::<Result<Vec<Reveal>, _>>()?;
+        Ok(Game { id, reveals })
     }
 }
 
@@ -67,7 +134,13 @@ impl FromStr for Puzzle {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Puzzle {
-            games: s.lines().map(str::parse).collect::<Result<Vec<_>, _>>()?,
+            games: s
+                .lines()
+                .map(|line| {
+                    line.parse()
+                        .map_err(|e: Oops| e.context(format!("{line:?}")))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
         })
     }
 }
@@ -76,34 +149,53 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn part1(puzzle: &Puzzle) -> u64 {
+// Sums the ids of the games that could have been played with a bag holding `limits`.
+fn possible_games(puzzle: &Puzzle, limits: Limits) -> u64 {
     puzzle
         .games
         .iter()
-        .filter(|game| game.red <= 12 && game.green <= 13 && game.blue <= 14)
+        .filter(|game| game.reveals.iter().all(|reveal| limits.allows(reveal)))
         .map(|game| game.id)
         .sum()
 }
 
+fn part1(puzzle: &Puzzle) -> u64 {
+    possible_games(puzzle, Limits::default())
+}
+
 fn part2(puzzle: &Puzzle) -> u64 {
     puzzle
         .games
         .iter()
-        .map(|game| game.red * game.green * game.blue)
+        .map(|game| {
+            let maxima = game.maxima();
+            maxima.red * maxima.green * maxima.blue
+        })
         .sum()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day02;
 
-    let puzzle = parse(&input)?;
+impl Solution for Day02 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part1(puzzle))
+    }
 
-    println!("{}", part1(&puzzle));
-    println!("{}", part2(&puzzle));
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part2(puzzle))
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day02>()
 }
 
 #[cfg(test)]
@@ -123,8 +215,99 @@ mod tests {
         assert_eq!(8, part1(&parse(SAMPLE).unwrap()));
     }
 
+    #[test]
+    fn stricter_limits() {
+        let limits = Limits {
+            red: 5,
+            green: 5,
+            blue: 5,
+        };
+        // Only game 2 stays within five of each color.
+        assert_eq!(2, possible_games(&parse(SAMPLE).unwrap(), limits));
+        // Game 1 shows six blue cubes at once.
+        let limits = Limits {
+            blue: 5,
+            ..Limits::default()
+        };
+        assert_eq!(2 + 5, possible_games(&parse(SAMPLE).unwrap(), limits));
+    }
+
     #[test]
     fn example2() {
         assert_eq!(2286, part2(&parse(SAMPLE).unwrap()));
     }
+
+    #[test]
+    fn irregular_whitespace() {
+        let game = "Game  3 :8 green,6 blue , 20 red;5 blue,  4   red; 13 green ;5 green;1 red"
+            .parse::<Game>()
+            .unwrap();
+        assert_eq!(3, game.id);
+        assert_eq!(
+            Reveal {
+                red: 20,
+                green: 13,
+                blue: 6
+            },
+            game.maxima()
+        );
+    }
+
+    #[test]
+    fn reveals() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(
+            vec![
+                Reveal {
+                    red: 4,
+                    green: 0,
+                    blue: 3
+                },
+                Reveal {
+                    red: 1,
+                    green: 2,
+                    blue: 6
+                },
+                Reveal {
+                    red: 0,
+                    green: 2,
+                    blue: 0
+                },
+            ],
+            puzzle.games[0].reveals
+        );
+    }
+
+    #[test]
+    fn reveal_counts() {
+        let puzzle = parse(SAMPLE).unwrap();
+        for (game, line) in std::iter::zip(&puzzle.games, SAMPLE.lines()) {
+            assert_eq!(line.matches(';').count() + 1, game.reveals.len(), "{line}");
+        }
+    }
+
+    #[test]
+    fn repeated_colour() {
+        assert_eq!(
+            Reveal {
+                red: 5,
+                green: 0,
+                blue: 2
+            },
+            "5 red, 2 blue, 3 red".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let error = parse("Game 1: 3 blue, red\n").unwrap_err().to_string();
+        assert_eq!(
+            "oops: \"Game 1: 3 blue, red\": expected a count and a colour, got \"red\"",
+            error
+        );
+        let error = parse("Game 2: -3 blue\n").unwrap_err().to_string();
+        assert!(error.contains("invalid count in \"-3 blue\""), "{error}");
+        let error = parse("Game 3: 3 purple\n").unwrap_err().to_string();
+        assert!(error.contains("unknown colour \"purple\""), "{error}");
+    }
 }
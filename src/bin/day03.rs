@@ -12,58 +12,77 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::geometry::Point2;
+use aoc_2023::geometry::{Bounds2, Point2};
+use aoc_2023::grid::parse_matrix;
+use aoc_2023::matrix::Matrix;
 use aoc_2023::oops::Oops;
-use aoc_2023::time;
-use std::collections::{HashMap, HashSet};
-use std::io::{self, Read};
+use aoc_2023::timing::{run_timed, Solution};
+use std::collections::HashMap;
 use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
-struct Id(u32);
+// A run of digits within a single row.
+#[derive(Debug)]
+struct Number {
+    value: u64,
+    start: Point2,
+    len: i32,
+}
+
+impl Number {
+    fn bounds(&self) -> Bounds2 {
+        Bounds2 {
+            min: self.start,
+            max: Point2::new(self.start.x + self.len - 1, self.start.y),
+        }
+    }
 
-#[derive(Clone, Copy, Debug)]
-enum Cell {
-    Number(Id),
-    Symbol(char),
+    fn points(&self) -> impl Iterator<Item = Point2> + '_ {
+        (0..self.len).map(|i| Point2::new(self.start.x + i, self.start.y))
+    }
 }
 
 #[derive(Debug)]
 struct Puzzle {
-    cells: HashMap<Point2, Cell>,
-    values: HashMap<Id, u64>,
+    grid: Matrix<char>,
+    // In reading order.
+    numbers: Vec<Number>,
+}
+
+impl Puzzle {
+    fn is_symbol_at(&self, p: Point2) -> bool {
+        self.grid
+            .get_point(p)
+            .is_some_and(|c| c != '.' && !c.is_ascii_digit())
+    }
 }
 
 impl FromStr for Puzzle {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut cells = HashMap::new();
-        let mut values = HashMap::<Id, u64>::new();
-        let mut next_id: Id = Id(0);
-        for (y, line) in s.lines().enumerate() {
-            for (x, c) in line.chars().enumerate() {
-                let (x, y) = (x.try_into()?, y.try_into()?);
-                if let Some(digit) = c.to_digit(10).map(u64::from) {
-                    let id = if let Some(Cell::Number(previous_id)) =
-                        cells.get(&Point2::new(x - 1, y))
-                    {
-                        *previous_id
-                    } else {
-                        next_id.0 += 1;
-                        next_id
-                    };
-                    cells.insert(Point2::new(x, y), Cell::Number(id));
-                    values
-                        .entry(id)
-                        .and_modify(|val| *val = *val * 10 + digit)
-                        .or_insert(digit);
-                } else if c != '.' {
-                    cells.insert(Point2::new(x, y), Cell::Symbol(c));
-                };
+        let grid = parse_matrix(s)?;
+        let mut numbers = vec![];
+        for y in 0..grid.height() {
+            let mut current: Option<Number> = None;
+            for (x, c) in grid.row(y).enumerate() {
+                match (c.to_digit(10).map(u64::from), current.as_mut()) {
+                    (Some(digit), Some(number)) => {
+                        number.value = number.value * 10 + digit;
+                        number.len += 1;
+                    }
+                    (Some(digit), None) => {
+                        current = Some(Number {
+                            value: digit,
+                            start: Point2::new(x.try_into()?, y.try_into()?),
+                            len: 1,
+                        });
+                    }
+                    (None, _) => numbers.extend(current.take()),
+                }
             }
+            numbers.extend(current);
         }
-        Ok(Puzzle { cells, values })
+        Ok(Puzzle { grid, numbers })
     }
 }
 
@@ -71,65 +90,93 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn part1(puzzle: &Puzzle) -> u64 {
+// The box covering each number's digits, keyed by the number's index in reading order.
+fn number_bounds(puzzle: &Puzzle) -> HashMap<usize, Bounds2> {
     puzzle
-        .cells
+        .numbers
         .iter()
-        .filter_map(|(p, &c)| {
-            let Cell::Number(value_id) = c else {
-                return None;
-            };
-            if p.all_neighbors()
-                .any(|neighbor| matches!(puzzle.cells.get(&neighbor), Some(Cell::Symbol(_))))
-            {
-                Some(value_id)
-            } else {
-                None
-            }
-        })
-        .collect::<HashSet<_>>()
-        .iter()
-        .map(|value_id| puzzle.values.get(value_id).unwrap())
-        .sum()
+        .map(Number::bounds)
+        .enumerate()
+        .collect()
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
+// The value of every number adjacent to a symbol, in reading order.
+fn part_numbers(puzzle: &Puzzle) -> Vec<u64> {
     puzzle
-        .cells
+        .numbers
         .iter()
-        .map(|(p, &cell)| match cell {
-            Cell::Symbol('*') => {
-                let ids = p
-                    .all_neighbors()
-                    .filter_map(|neighbor| match puzzle.cells.get(&neighbor) {
-                        Some(Cell::Number(value_id)) => Some(value_id),
-                        _ => None,
-                    })
-                    .collect::<HashSet<_>>();
-                match ids.len() {
-                    2 => ids
-                        .into_iter()
-                        .map(|id| puzzle.values.get(id).unwrap())
-                        .product(),
-                    _ => 0,
+        .filter(|number| {
+            number
+                .points()
+                .any(|p| p.all_neighbors().any(|n| puzzle.is_symbol_at(n)))
+        })
+        .map(|number| number.value)
+        .collect()
+}
+
+// Each `*` that touches exactly two numbers, with those numbers in reading order. Gears are
+// listed in reading order too.
+fn gears(puzzle: &Puzzle) -> Vec<(Point2, u64, u64)> {
+    let bounds = number_bounds(puzzle);
+    // The numbers around each `*`. Numbers are visited in reading order, so each list is too.
+    let mut neighbors: HashMap<Point2, Vec<u64>> = HashMap::new();
+    for (i, number) in puzzle.numbers.iter().enumerate() {
+        let around = bounds[&i].outset(1);
+        for y in around.min.y..=around.max.y {
+            for x in around.min.x..=around.max.x {
+                let p = Point2::new(x, y);
+                if puzzle.grid.get_point(p) == Some('*') {
+                    neighbors.entry(p).or_default().push(number.value);
                 }
             }
-            _ => 0,
+        }
+    }
+    let mut gears = neighbors
+        .into_iter()
+        .filter_map(|(p, values)| match values[..] {
+            [a, b] => Some((p, a, b)),
+            _ => None,
         })
-        .sum()
+        .collect::<Vec<_>>();
+    gears.sort_unstable_by_key(|(p, _, _)| (p.y, p.x));
+    gears
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+// The product of the two numbers next to each gear, in reading order.
+fn gear_ratios(puzzle: &Puzzle) -> Vec<u64> {
+    gears(puzzle).iter().map(|(_, a, b)| a * b).collect()
+}
+
+fn part1(puzzle: &Puzzle) -> u64 {
+    part_numbers(puzzle).iter().sum()
+}
+
+fn part2(puzzle: &Puzzle) -> u64 {
+    gear_ratios(puzzle).iter().sum()
+}
+
+struct Day03;
+
+impl Solution for Day03 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
 
-    let puzzle = time!(parse(&input)?);
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part1(puzzle))
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part2(puzzle))
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day03>()
 }
 
 #[cfg(test)]
@@ -158,4 +205,78 @@ mod tests {
     fn example2() {
         assert_eq!(467835, part2(&parse(SAMPLE).unwrap()));
     }
+
+    #[test]
+    fn bounds() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let bounds = number_bounds(&puzzle);
+        assert_eq!(10, bounds.len());
+        assert_eq!(467, puzzle.numbers[0].value);
+        assert_eq!(Point2::new(0, 0), bounds[&0].min);
+        assert_eq!(Point2::new(2, 0), bounds[&0].max);
+        assert_eq!(3, bounds[&0].width());
+        assert_eq!(1, bounds[&0].height());
+    }
+
+    #[test]
+    fn single_digit_bounds() {
+        let puzzle = parse("..\n.7\n").unwrap();
+        let bounds = number_bounds(&puzzle);
+        assert_eq!(1, bounds.len());
+        let bounds = bounds.values().next().unwrap();
+        assert_eq!(Point2::new(1, 1), bounds.min);
+        assert_eq!(Point2::new(1, 1), bounds.max);
+    }
+
+    #[test]
+    fn diagonal_symbol() {
+        const GRID: &str = concat!(
+            "12....\n", //
+            "..#.7.\n", //
+            "......\n", //
+            "....34\n", //
+        );
+        assert_eq!(vec![12], part_numbers(&parse(GRID).unwrap()));
+    }
+
+    #[test]
+    fn number_at_end_of_row() {
+        assert_eq!(vec![45], part_numbers(&parse("..45\n.$..\n").unwrap()));
+    }
+
+    #[test]
+    fn part_number_list() {
+        assert_eq!(
+            vec![467, 35, 633, 617, 592, 755, 664, 598],
+            part_numbers(&parse(SAMPLE).unwrap())
+        );
+    }
+
+    #[test]
+    fn gear_list() {
+        assert_eq!(
+            vec![(Point2::new(3, 1), 467, 35), (Point2::new(5, 8), 755, 598)],
+            gears(&parse(SAMPLE).unwrap())
+        );
+    }
+
+    #[test]
+    fn gear_neighbor_counts() {
+        // Three numbers around the `*`, then only one.
+        assert!(gears(&parse("1*2\n.3.\n").unwrap()).is_empty());
+        assert!(gears(&parse("1*.\n...\n").unwrap()).is_empty());
+        // A number touching the `*` with several digits still counts once.
+        assert_eq!(
+            vec![(Point2::new(1, 1), 12, 3)],
+            gears(&parse("12.\n.*.\n..3\n").unwrap())
+        );
+    }
+
+    #[test]
+    fn gear_ratio_list() {
+        assert_eq!(
+            vec![467 * 35, 755 * 598],
+            gear_ratios(&parse(SAMPLE).unwrap())
+        );
+    }
 }
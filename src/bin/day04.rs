@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
 use std::collections::HashSet;
-use std::io::{self, Read};
 use std::str::FromStr;
 
 struct Card {
@@ -43,6 +43,16 @@ impl FromStr for Card {
     }
 }
 
+impl Card {
+    // How many of the numbers we have are winning numbers.
+    fn matches(&self) -> usize {
+        self.have
+            .iter()
+            .filter(|n| self.winning.contains(n))
+            .count()
+    }
+}
+
 struct Puzzle {
     cards: Vec<Card>,
 }
@@ -61,45 +71,72 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn part1(puzzle: &Puzzle) -> u64 {
-    puzzle
-        .cards
-        .iter()
-        .map(
-            |c| match c.have.iter().filter(|n| c.winning.contains(n)).count() {
-                0 => 0,
-                count => 2u64.pow((count - 1).try_into().unwrap()),
-            },
-        )
-        .sum()
+fn match_counts(puzzle: &Puzzle) -> Vec<usize> {
+    puzzle.cards.iter().map(Card::matches).collect()
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
-    let winning_counts = puzzle
-        .cards
-        .iter()
-        .map(|c| c.have.iter().filter(|n| c.winning.contains(n)).count());
-    let mut copies = vec![1; winning_counts.len()];
-    for (idx, count) in winning_counts.enumerate() {
+// How many copies of each card end up being scratched, counting the original.
+fn card_copies(puzzle: &Puzzle) -> Result<Vec<u64>, Oops> {
+    let winning_counts = match_counts(puzzle);
+    let mut copies = vec![1u64; winning_counts.len()];
+    for (idx, count) in winning_counts.into_iter().enumerate() {
         let current = copies[idx];
         for copy_idx in idx + 1..std::cmp::min(idx + 1 + count, copies.len()) {
-            copies[copy_idx] += current;
+            copies[copy_idx] = copies[copy_idx]
+                .checked_add(current)
+                .ok_or_else(|| oops!("too many copies of card {}", copy_idx + 1))?;
         }
     }
-    copies.iter().sum()
+    Ok(copies)
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+    match_counts(puzzle)
+        .into_iter()
+        .enumerate()
+        .map(|(idx, count)| match count {
+            0 => Ok(0),
+            count => u32::try_from(count - 1)
+                .ok()
+                .and_then(|shift| 1u64.checked_shl(shift))
+                .ok_or_else(|| oops!("card {} is worth too many points", idx + 1)),
+        })
+        .try_fold(0u64, |total, points| {
+            total
+                .checked_add(points?)
+                .ok_or_else(|| oops!("too many points in total"))
+        })
+}
 
-    let puzzle = parse(&input)?;
+fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+    card_copies(puzzle)?
+        .iter()
+        .try_fold(0u64, |total, count| total.checked_add(*count))
+        .ok_or_else(|| oops!("too many cards in total"))
+}
+
+struct Day04;
+
+impl Solution for Day04 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part1(puzzle)
+    }
 
-    println!("{}", part1(&puzzle));
-    println!("{}", part2(&puzzle));
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part2(puzzle)
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day04>()
 }
 
 #[cfg(test)]
@@ -117,11 +154,53 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(13, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(13, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
-        assert_eq!(30, part2(&parse(SAMPLE).unwrap()));
+        assert_eq!(30, part2(&parse(SAMPLE).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn card_matches() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(vec![4, 2, 2, 1, 0, 0], match_counts(&puzzle));
+    }
+
+    #[test]
+    fn copies() {
+        assert_eq!(
+            vec![1, 2, 4, 8, 14, 1],
+            card_copies(&parse(SAMPLE).unwrap()).unwrap()
+        );
+    }
+
+    // Every card matches all of its numbers, so each card wins a copy of every later card and card
+    // n ends up with 2^(n - 1) copies.
+    fn all_winning(cards: usize) -> String {
+        let numbers = (1..=cards).map(|n| n.to_string()).collect::<Vec<_>>();
+        let numbers = numbers.join(" ");
+        (1..=cards)
+            .map(|id| format!("Card {id}: {numbers} | {numbers}\n"))
+            .collect()
+    }
+
+    #[test]
+    fn copies_overflow() {
+        assert_eq!(u64::MAX, part2(&parse(&all_winning(64)).unwrap()).unwrap());
+        assert!(part2(&parse(&all_winning(65)).unwrap()).is_err());
+    }
+
+    #[test]
+    fn points_overflow() {
+        let card = |matches: usize| {
+            let numbers = (1..=matches).map(|n| n.to_string()).collect::<Vec<_>>();
+            let numbers = numbers.join(" ");
+            format!("Card 1: {numbers} | {numbers}\n")
+        };
+        assert_eq!(1 << 63, part1(&parse(&card(64)).unwrap()).unwrap());
+        assert!(part1(&parse(&card(65)).unwrap()).is_err());
+        assert!(part1(&parse(&all_winning(64)).unwrap()).is_err());
     }
 }
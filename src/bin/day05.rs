@@ -12,11 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::time;
+use aoc_2023::io_util::{flag, verbose};
+use aoc_2023::parallel::par_map;
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
-use std::io::{self, Read};
 use std::str::FromStr;
 
 #[derive(Debug, Eq, PartialEq)]
@@ -30,10 +31,6 @@ impl Range {
     fn contains_position(&self, position: u64) -> bool {
         self.begin <= position && position < self.end
     }
-
-    fn contains_range(&self, other: &Self) -> bool {
-        self.begin <= other.begin && other.end <= self.end
-    }
 }
 
 impl Ord for Range {
@@ -125,110 +122,232 @@ fn part1(puzzle: &Puzzle) -> u64 {
         .expect("no seeds")
 }
 
-fn apply_mapping_to_ranges(ranges: Vec<Range>, mapping: &BTreeMap<Range, u64>) -> Vec<Range> {
-    let mut new_ranges = vec![];
-    for original in ranges {
-        let overlapping_ranges = mapping
-            .range(
-                Range {
-                    begin: original.begin,
-                    end: original.begin,
-                }..,
-            )
-            .collect::<Vec<_>>();
+// Returns every value that `mapping` sends to `dst`: `dst` itself if it isn't covered by a source
+// range, plus the corresponding position in each source range whose destination covers `dst`.
+fn preimages(dst: u64, mapping: &BTreeMap<Range, u64>) -> Vec<u64> {
+    std::iter::once(dst)
+        .chain(mapping.iter().filter_map(|(src_range, &dst_begin)| {
+            let offset = dst.checked_sub(dst_begin)?;
+            if offset < src_range.end - src_range.begin {
+                Some(src_range.begin + offset)
+            } else {
+                None
+            }
+        }))
+        .filter(|src| apply_mapping(*src, mapping) == dst)
+        .collect()
+}
 
-        if overlapping_ranges.is_empty() {
-            // Not covered by mapping; map directly through.
-            new_ranges.push(original);
-            continue;
-        }
+// Applies the mappings in reverse, returning every seed that ends up at `location` in ascending
+// order. The seeds need not be ones listed in the puzzle.
+fn location_to_seeds(puzzle: &Puzzle, location: u64) -> Vec<u64> {
+    puzzle
+        .mappings
+        .iter()
+        .rev()
+        .fold(vec![location], |dsts, mapping| {
+            let mut srcs = dsts
+                .into_iter()
+                .flat_map(|dst| preimages(dst, mapping))
+                .collect::<Vec<_>>();
+            srcs.sort_unstable();
+            srcs.dedup();
+            srcs
+        })
+}
 
-        if let Some((first_overlapping, _first_dest)) = overlapping_ranges.first() {
-            // Not covered by mapping; map directly through.
-            if original.begin < first_overlapping.begin {
-                new_ranges.push(Range {
-                    begin: original.begin,
-                    end: std::cmp::min(original.end, first_overlapping.begin),
-                });
-            }
-        }
+// The lowest seed that ends up at `location`, or None if no seed does.
+fn location_to_seed(puzzle: &Puzzle, location: u64) -> Option<u64> {
+    location_to_seeds(puzzle, location).first().copied()
+}
 
-        for (overlapping, &dest) in &overlapping_ranges {
-            if original.end < overlapping.begin {
-                break;
-            } else if overlapping.contains_range(&original) {
-                // `original` is wholly contained in `overlapping`
-                let begin = original.begin - overlapping.begin + dest;
-                let end = original.end - overlapping.begin + dest;
-                new_ranges.push(Range { begin, end });
-                break;
-            } else if original.contains_range(overlapping) {
-                //
-                let begin = dest;
-                let end = dest + overlapping.end - overlapping.begin;
-                new_ranges.push(Range { begin, end });
-            } else if overlapping.contains_position(original.begin) {
-                let begin = dest + original.begin - overlapping.begin;
-                let end = begin + overlapping.end - original.begin;
-                new_ranges.push(Range { begin, end });
-            } else if overlapping.contains_position(original.end) {
-                let begin = dest;
-                let end = dest + original.end - overlapping.begin;
-                new_ranges.push(Range { begin, end });
+// Splits each range at the mapping's source boundaries, translating the pieces covered by a source
+// range and passing the rest (before, between or after the source ranges) through unchanged.
+fn apply_mapping_to_ranges(ranges: Vec<Range>, mapping: &BTreeMap<Range, u64>) -> Vec<Range> {
+    let mut new_ranges = vec![];
+    for original in ranges {
+        // Everything in `original` before `cursor` has already been emitted.
+        let mut cursor = original.begin;
+        let first_candidate = Range {
+            begin: original.begin,
+            end: original.begin,
+        };
+        for (src, &dest) in mapping.range(first_candidate..) {
+            if src.begin >= original.end {
                 break;
-            } else {
-                unreachable!();
             }
-        }
-
-        if let Some((last_overlapping, _last_dest)) = overlapping_ranges.last() {
-            if original.end > last_overlapping.end {
+            let begin = std::cmp::max(src.begin, cursor);
+            let end = std::cmp::min(src.end, original.end);
+            if begin >= end {
+                continue;
+            }
+            if cursor < begin {
+                // Not covered by mapping; map directly through.
                 new_ranges.push(Range {
-                    begin: std::cmp::max(original.begin, last_overlapping.end),
-                    end: original.end,
+                    begin: cursor,
+                    end: begin,
                 });
             }
+            new_ranges.push(Range {
+                begin: begin - src.begin + dest,
+                end: end - src.begin + dest,
+            });
+            cursor = end;
+        }
+        if cursor < original.end {
+            new_ranges.push(Range {
+                begin: cursor,
+                end: original.end,
+            });
         }
     }
     new_ranges
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
+// Sorts `ranges` and merges any that overlap or touch, dropping empty ranges.
+fn coalesce(mut ranges: Vec<Range>) -> Vec<Range> {
+    ranges.sort_unstable_by_key(|range| range.begin);
+    let mut coalesced: Vec<Range> = vec![];
+    for range in ranges.into_iter().filter(|range| range.begin < range.end) {
+        match coalesced.last_mut() {
+            Some(last) if range.begin <= last.end => last.end = std::cmp::max(last.end, range.end),
+            _ => coalesced.push(range),
+        }
+    }
+    coalesced
+}
+
+fn seed_ranges(puzzle: &Puzzle) -> Vec<Range> {
     std::iter::zip(
         puzzle.seeds.iter().step_by(2),
         puzzle.seeds.iter().skip(1).step_by(2),
     )
-    .map(|(seed, range)| {
-        let mut current_ranges = vec![Range {
-            begin: *seed,
-            end: *seed + *range,
-        }];
+    .map(|(seed, range)| Range {
+        begin: *seed,
+        end: *seed + *range,
+    })
+    .collect()
+}
 
-        for mapping in &puzzle.mappings {
-            current_ranges = apply_mapping_to_ranges(current_ranges, mapping);
-        }
+fn map_ranges(puzzle: &Puzzle, ranges: Vec<Range>) -> Vec<Range> {
+    puzzle
+        .mappings
+        .iter()
+        .fold(coalesce(ranges), |ranges, mapping| {
+            coalesce(apply_mapping_to_ranges(ranges, mapping))
+        })
+}
 
-        current_ranges
-            .into_iter()
+// Returns every location reachable from the seed ranges, sorted and coalesced.
+fn mapped_location_ranges(puzzle: &Puzzle) -> Vec<Range> {
+    map_ranges(puzzle, seed_ranges(puzzle))
+}
+
+// Maps one seed range at a time, stopping as soon as any seed reaches location 0.
+fn min_location_sequential(puzzle: &Puzzle) -> u64 {
+    let mut min_location = None;
+    for range in seed_ranges(puzzle) {
+        let Some(location) = map_ranges(puzzle, vec![range])
+            .first()
             .map(|range| range.begin)
-            .min()
-            .unwrap()
+        else {
+            continue;
+        };
+        if location == 0 {
+            // Nothing can map to a lower location, so skip the remaining seeds.
+            return 0;
+        }
+        min_location = Some(min_location.map_or(location, |min| std::cmp::min(min, location)));
+    }
+    min_location.expect("no seeds")
+}
+
+// Maps each seed range on its own thread.
+fn min_location_parallel(puzzle: &Puzzle) -> u64 {
+    par_map(&seed_ranges(puzzle), |range| {
+        let ranges = vec![Range {
+            begin: range.begin,
+            end: range.end,
+        }];
+        map_ranges(puzzle, ranges).first().map(|range| range.begin)
     })
+    .into_iter()
+    .flatten()
     .min()
     .expect("no seeds")
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+// Tries each location in ascending order until one maps back into the seed ranges. Much slower
+// than mapping the ranges forward, but it shares none of the range-splitting logic, so it's a
+// useful cross-check.
+fn min_location_reverse(puzzle: &Puzzle) -> Option<u64> {
+    let seed_ranges = seed_ranges(puzzle);
+    if seed_ranges.iter().all(|range| range.begin == range.end) {
+        return None;
+    }
+    (0..).find(|location| {
+        location_to_seeds(puzzle, *location)
+            .into_iter()
+            .any(|seed| {
+                seed_ranges
+                    .iter()
+                    .any(|range| range.contains_position(seed))
+            })
+    })
+}
+
+fn part2(puzzle: &Puzzle) -> u64 {
+    if cfg!(feature = "parallel") {
+        min_location_parallel(puzzle)
+    } else {
+        min_location_sequential(puzzle)
+    }
+}
+
+// With --reverse, part2 searches upwards from location 0 instead of mapping the seed ranges
+// forwards. With --verbose, it also reports which seed reaches the answer.
+fn part2_with_flags(puzzle: &Puzzle) -> Result<u64, Oops> {
+    let location = if flag("--reverse") {
+        min_location_reverse(puzzle).ok_or_else(|| oops!("no seeds"))?
+    } else {
+        part2(puzzle)
+    };
+    if verbose() {
+        let reachable = mapped_location_ranges(puzzle)
+            .iter()
+            .map(|range| range.end - range.begin)
+            .sum::<u64>();
+        println!("{reachable} locations are reachable");
+        match location_to_seed(puzzle, location) {
+            Some(seed) => println!("reached from seed {seed}"),
+            None => println!("no seed reaches location {location}"),
+        }
+    }
+    Ok(location)
+}
+
+struct Day05;
+
+impl Solution for Day05 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
 
-    let puzzle = time!(parse(&input)?);
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part1(puzzle))
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part2_with_flags(puzzle)
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day05>()
 }
 
 #[cfg(test)]
@@ -280,4 +399,182 @@ mod tests {
     fn example2() {
         assert_eq!(46, part2(&parse(SAMPLE).unwrap()));
     }
+
+    #[test]
+    fn part2_location_zero() {
+        const SAMPLE: &str = concat!(
+            "seeds: 5 3 100 10\n", //
+            "\n",
+            "seed-to-soil map:\n",
+            "0 5 1\n",
+        );
+        assert_eq!(0, part2(&parse(SAMPLE).unwrap()));
+    }
+
+    // Seven stages, each splitting 0..10000 into consecutive source ranges with pseudo-random
+    // lengths and destinations.
+    fn synthetic_input() -> String {
+        let mut state = 42u64;
+        let mut next = |modulus: u64| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (state >> 33) % modulus
+        };
+        let seeds = (0..20)
+            .map(|_| format!("{} {}", next(9000), next(1000) + 1))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut input = format!("seeds: {seeds}\n");
+        for stage in 0..7 {
+            input += &format!("\nstage{stage} map:\n");
+            let mut src = next(50);
+            while src < 10000 {
+                let len = next(700) + 1;
+                // Leave some gaps so unmapped values pass straight through.
+                if next(4) != 0 {
+                    input += &format!("{} {src} {len}\n", next(10000));
+                }
+                src += len;
+            }
+        }
+        input
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(46, min_location_sequential(&puzzle));
+        assert_eq!(46, min_location_parallel(&puzzle));
+
+        let puzzle = parse(&synthetic_input()).unwrap();
+        let brute_force = seed_ranges(&puzzle)
+            .iter()
+            .flat_map(|range| range.begin..range.end)
+            .map(|seed| puzzle.mappings.iter().fold(seed, apply_mapping))
+            .min()
+            .unwrap();
+        assert_eq!(brute_force, min_location_sequential(&puzzle));
+        assert_eq!(brute_force, min_location_parallel(&puzzle));
+    }
+
+    #[test]
+    fn reverse_matches_forward() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(Some(46), min_location_reverse(&puzzle));
+
+        let puzzle = parse(&synthetic_input()).unwrap();
+        assert_eq!(
+            Some(min_location_sequential(&puzzle)),
+            min_location_reverse(&puzzle)
+        );
+
+        // An empty seed range has no locations at all.
+        let puzzle = parse("seeds: 3 0\n\nseed-to-soil map:\n0 5 1\n").unwrap();
+        assert_eq!(None, min_location_reverse(&puzzle));
+    }
+
+    #[test]
+    fn location_ranges() {
+        let ranges = mapped_location_ranges(&parse(SAMPLE).unwrap());
+        assert_eq!(46, ranges[0].begin);
+        assert!(ranges.iter().any(|range| range.contains_position(46)));
+        assert!(ranges.windows(2).all(|w| w[0].end < w[1].begin));
+        // Every seed maps to exactly one location, so no locations are lost or gained.
+        assert_eq!(
+            14 + 13,
+            ranges
+                .iter()
+                .map(|range| range.end - range.begin)
+                .sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn coalesce_after_mapping() {
+        let mapping = parse_mappings("a-to-b map:\n10 20 5\n").unwrap();
+        let ranges = vec![
+            Range { begin: 15, end: 18 },
+            Range { begin: 20, end: 25 },
+            Range { begin: 10, end: 13 },
+            Range { begin: 30, end: 30 },
+            Range { begin: 40, end: 42 },
+        ];
+        // 20..25 maps onto 10..15, overlapping 10..13 and touching 15..18.
+        assert_eq!(
+            vec![Range { begin: 10, end: 18 }, Range { begin: 40, end: 42 }],
+            coalesce(apply_mapping_to_ranges(ranges, &mapping))
+        );
+    }
+
+    // Maps every position in `range` one at a time and checks that the split ranges cover exactly
+    // the same locations.
+    fn assert_splits_correctly(mapping: &BTreeMap<Range, u64>, range: Range) {
+        let mut expected = (range.begin..range.end)
+            .map(|src| apply_mapping(src, mapping))
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+        let mut actual = apply_mapping_to_ranges(vec![range], mapping)
+            .into_iter()
+            .flat_map(|range| range.begin..range.end)
+            .collect::<Vec<_>>();
+        actual.sort_unstable();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn split_at_mapping_boundaries() {
+        // Maps 10..20 to 100..110 and 30..40 to 200..210, leaving 20..30 unmapped.
+        let mapping = parse_mappings("a-to-b map:\n100 10 10\n200 30 10\n").unwrap();
+        // Starting exactly at a mapping's begin.
+        assert_splits_correctly(&mapping, Range { begin: 10, end: 15 });
+        assert_splits_correctly(&mapping, Range { begin: 10, end: 25 });
+        // Ending exactly at a mapping's end.
+        assert_splits_correctly(&mapping, Range { begin: 5, end: 20 });
+        assert_splits_correctly(&mapping, Range { begin: 15, end: 20 });
+        assert_splits_correctly(&mapping, Range { begin: 25, end: 40 });
+        // Straddling both entries and the gap between them.
+        assert_splits_correctly(&mapping, Range { begin: 15, end: 35 });
+        assert_splits_correctly(&mapping, Range { begin: 5, end: 45 });
+        assert_splits_correctly(&mapping, Range { begin: 10, end: 40 });
+        // Entirely within the gap, or past every entry.
+        assert_splits_correctly(&mapping, Range { begin: 20, end: 30 });
+        assert_splits_correctly(&mapping, Range { begin: 40, end: 50 });
+        assert_eq!(
+            vec![
+                Range {
+                    begin: 105,
+                    end: 110
+                },
+                Range { begin: 20, end: 30 },
+                Range {
+                    begin: 200,
+                    end: 205
+                },
+            ],
+            apply_mapping_to_ranges(vec![Range { begin: 15, end: 35 }], &mapping)
+        );
+    }
+
+    #[test]
+    fn location_to_seed_round_trip() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let seed_to_location = |seed| puzzle.mappings.iter().fold(seed, apply_mapping);
+        for seed in 0..120 {
+            let location = seed_to_location(seed);
+            let reversed = location_to_seed(&puzzle, location).unwrap();
+            assert!(reversed <= seed);
+            assert_eq!(location, seed_to_location(reversed));
+        }
+        // Seed 13 has the lowest location of the listed seeds.
+        assert_eq!(Some(13), location_to_seed(&puzzle, 35));
+    }
+
+    #[test]
+    fn location_without_seed() {
+        // 5 maps to 0, so nothing maps to 5.
+        let puzzle = parse("seeds: 1\n\nseed-to-soil map:\n0 5 1\n").unwrap();
+        assert_eq!(None, location_to_seed(&puzzle, 5));
+        // Both 0 and 5 map to 0.
+        assert_eq!(Some(0), location_to_seed(&puzzle, 0));
+        assert_eq!(vec![0, 5], preimages(0, &puzzle.mappings[0]));
+    }
 }
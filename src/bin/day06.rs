@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::time;
+use aoc_2023::math::solve_quadratic;
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
-use std::io::{self, Read};
 use std::str::FromStr;
 
 struct Race {
@@ -71,42 +71,85 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
+impl Race {
+    fn wins(&self, pressed_time: u64) -> bool {
+        pressed_time <= self.time && (self.time - pressed_time) * pressed_time > self.distance
+    }
+
+    // Estimates the first and last winning hold times from the roots of
+    // t^2 - time * t + distance = 0. ways_to_win corrects the estimate with exact arithmetic, so
+    // the lossy u64 <-> f64 casts (which saturate rather than wrap) only need to land nearby.
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    fn estimate_winning_range(&self) -> Option<(u64, u64)> {
+        let (low, high) = solve_quadratic(1.0, -(self.time as f64), self.distance as f64)?;
+        Some((
+            (low.floor().max(0.0) as u64).saturating_add(1),
+            (high.ceil().max(0.0) as u64).saturating_sub(1),
+        ))
+    }
+
+    // Counts the hold times t where (time - t) * t > distance, i.e. the integers strictly between
+    // the roots of t^2 - time * t + distance = 0.
+    fn ways_to_win(&self) -> u64 {
+        let Some((mut first, mut last)) = self.estimate_winning_range() else {
+            return 0;
+        };
+        // Roots that are exact integers tie rather than win, and floating point error could put
+        // the estimate off by one either way, so settle the boundaries with exact arithmetic.
+        while first > 0 && self.wins(first - 1) {
+            first -= 1;
+        }
+        while first <= last && !self.wins(first) {
+            first += 1;
+        }
+        while self.wins(last + 1) {
+            last += 1;
+        }
+        while last >= first && !self.wins(last) {
+            last -= 1;
+        }
+        if first > last {
+            0
+        } else {
+            last - first + 1
+        }
+    }
+}
+
 fn part1(puzzle: &Puzzle) -> u64 {
-    puzzle
-        .records1
-        .iter()
-        .map(|r| {
-            u64::try_from(
-                (0..r.time)
-                    .filter(|pressed_time| (r.time - pressed_time) * (pressed_time) > r.distance)
-                    .count(),
-            )
-            .unwrap()
-        })
-        .product()
+    puzzle.records1.iter().map(Race::ways_to_win).product()
 }
 
 fn part2(puzzle: &Puzzle) -> u64 {
-    (0..puzzle.record2.time)
-        .filter(|pressed_time| {
-            (puzzle.record2.time - pressed_time) * (pressed_time) > puzzle.record2.distance
-        })
-        .count()
-        .try_into()
-        .unwrap()
+    puzzle.record2.ways_to_win()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day06;
+
+impl Solution for Day06 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
 
-    let puzzle = time!(parse(&input)?);
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part1(puzzle))
+    }
 
-    Ok(())
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part2(puzzle))
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day06>()
 }
 
 #[cfg(test)]
@@ -127,4 +170,19 @@ mod tests {
     fn example2() {
         assert_eq!(71503, part2(&parse(SAMPLE).unwrap()));
     }
+
+    #[test]
+    fn ways_to_win_matches_brute_force() {
+        for time in 0..60 {
+            for distance in 0..=time * time / 4 + 1 {
+                let race = Race { time, distance };
+                let brute_force = (0..=time).filter(|t| race.wins(*t)).count();
+                assert_eq!(
+                    u64::try_from(brute_force).unwrap(),
+                    race.ways_to_win(),
+                    "time {time} distance {distance}"
+                );
+            }
+        }
+    }
 }
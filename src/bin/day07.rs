@@ -12,10 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::time;
+use aoc_2023::collections::count_signature;
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
 use std::collections::HashMap;
-use std::io::{self, Read};
 use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
@@ -47,22 +47,75 @@ enum Rank {
     HighCard = 1,
 }
 
-fn with_jokers(mut cards: [Card; 5]) -> [Card; 5] {
-    for card in &mut cards {
-        if *card == Card::J {
-            *card = Card::Joker;
+impl Card {
+    fn label(self) -> char {
+        match self {
+            Card::A => 'A',
+            Card::K => 'K',
+            Card::Q => 'Q',
+            Card::J | Card::Joker => 'J',
+            Card::T => 'T',
+            Card::Nine => '9',
+            Card::Eight => '8',
+            Card::Seven => '7',
+            Card::Six => '6',
+            Card::Five => '5',
+            Card::Four => '4',
+            Card::Three => '3',
+            Card::Two => '2',
         }
     }
-    cards
 }
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Ord)]
+// Every card label, weakest first under the standard rules.
+const LABELS: &str = "23456789TJQKA";
+
+// How strong each card label is when breaking ties, and which label (if any) is wild when
+// classifying a hand.
+#[derive(Debug)]
+struct Strengths {
+    values: HashMap<char, u8>,
+    wild: Option<char>,
+}
+
+impl Strengths {
+    fn new(values: HashMap<char, u8>, wild: Option<char>) -> Result<Self, Oops> {
+        if let Some(label) = LABELS.chars().find(|label| !values.contains_key(label)) {
+            return Err(oops!("no strength for card {label:?}"));
+        }
+        if let Some(label) = wild.filter(|label| !LABELS.contains(*label)) {
+            return Err(oops!("wild card {label:?} isn't a card"));
+        }
+        Ok(Strengths { values, wild })
+    }
+
+    fn strength(&self, card: Card) -> u8 {
+        // Strengths::new checked that every label has a strength.
+        self.values[&card.label()]
+    }
+
+    fn is_wild(&self, card: Card) -> bool {
+        self.wild == Some(card.label())
+    }
+}
+
+fn standard_strengths() -> Result<Strengths, Oops> {
+    Strengths::new(std::iter::zip(LABELS.chars(), 2u8..).collect(), None)
+}
+
+// Jokers are wild, but are the weakest card when breaking ties.
+fn joker_strengths() -> Result<Strengths, Oops> {
+    let mut values = std::iter::zip(LABELS.chars(), 2u8..).collect::<HashMap<_, _>>();
+    values.insert('J', 1);
+    Strengths::new(values, Some('J'))
+}
+
+#[derive(Debug)]
 struct Hand {
-    rank: Rank,
     cards: [Card; 5],
 }
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Debug)]
 struct Line {
     hand: Hand,
     bid: u64,
@@ -71,63 +124,35 @@ struct Line {
 #[derive(Debug)]
 struct Puzzle {
     lines: Vec<Line>,
-    joker_lines: Vec<Line>,
-}
-
-fn classify(cards: [Card; 5]) -> Rank {
-    let unique = cards.iter().fold(HashMap::new(), |mut map, card| {
-        map.entry(*card)
-            .and_modify(|count| *count += 1)
-            .or_insert(1);
-        map
-    });
-    match unique.len() {
-        5 => Rank::HighCard,
-        4 => Rank::OnePair,
-        3 => {
-            if unique.iter().any(|(_, &count)| count == 3) {
-                Rank::Triple
-            } else {
-                Rank::TwoPair
-            }
-        }
-        2 => {
-            if unique.iter().any(|(_, &count)| count == 1) {
-                Rank::FourOfAKind
-            } else {
-                Rank::FullHouse
-            }
-        }
-        1 => Rank::FiveOfAKind,
-        _ => panic!("umm"),
+}
+
+// Ranks a hand by the sizes of its groups of identical cards, largest first.
+fn classify(signature: &[usize]) -> Rank {
+    match signature {
+        [5] => Rank::FiveOfAKind,
+        [4, 1] => Rank::FourOfAKind,
+        [3, 2] => Rank::FullHouse,
+        [3, 1, 1] => Rank::Triple,
+        [2, 2, 1] => Rank::TwoPair,
+        [2, 1, 1, 1] => Rank::OnePair,
+        [1, 1, 1, 1, 1] => Rank::HighCard,
+        _ => unreachable!(),
     }
 }
 
+// Jokers always do best by joining the largest group of other cards.
 fn classify_joker(cards: [Card; 5]) -> Rank {
-    let mut cards = cards;
-    cards.sort();
-    // Find the most common card
-    let (jokers, max_card, _, _, _) = cards.iter().fold(
-        (0, Card::Joker, 0, Card::Joker, 0),
-        |(jokers, max_card, max_len, cur_card, cur_len), &card| {
-            if card == Card::Joker {
-                (jokers + 1, Card::Joker, jokers + 1, Card::Joker, 0)
-            } else if card == max_card {
-                (jokers, max_card, max_len + 1, max_card, max_len + 1)
-            } else if card == cur_card {
-                if cur_len + 1 >= max_len {
-                    (jokers, cur_card, cur_len + 1, cur_card, cur_len + 1)
-                } else {
-                    (jokers, max_card, max_len, cur_card, cur_len + 1)
-                }
-            } else {
-                (jokers, max_card, max_len, card, 1)
-            }
-        },
-    );
-    // Technically unnecessary for a hand of all jokers, but also harmless.
-    cards[0..jokers].fill(max_card);
-    classify(cards)
+    let others = cards
+        .into_iter()
+        .filter(|card| *card != Card::Joker)
+        .collect::<Vec<_>>();
+    let jokers = cards.len() - others.len();
+    let mut signature = count_signature(&others);
+    match signature.first_mut() {
+        Some(largest) => *largest += jokers,
+        None => signature.push(jokers),
+    }
+    classify(&signature)
 }
 
 impl FromStr for Hand {
@@ -156,8 +181,7 @@ impl FromStr for Hand {
                 _ => return Err(oops!("bad card")),
             };
         }
-        let rank = classify(cards);
-        Ok(Hand { rank, cards })
+        Ok(Hand { cards })
     }
 }
 
@@ -179,21 +203,9 @@ impl FromStr for Puzzle {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines().map(str::parse).collect::<Result<Vec<_>, _>>()?;
-        let mut joker_lines = lines
-            .iter()
-            .map(|line: &Line| {
-                let cards = with_jokers(line.hand.cards);
-                let rank = classify_joker(cards);
-                Line {
-                    hand: Hand { rank, cards },
-                    bid: line.bid,
-                }
-            })
-            .collect::<Vec<_>>();
-        lines.sort();
-        joker_lines.sort();
-        Ok(Puzzle { lines, joker_lines })
+        Ok(Puzzle {
+            lines: s.lines().map(str::parse).collect::<Result<Vec<_>, _>>()?,
+        })
     }
 }
 
@@ -201,29 +213,58 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn part1(puzzle: &Puzzle) -> u64 {
-    std::iter::zip(1u64.., puzzle.lines.iter())
-        .map(|(i, line)| line.bid * i)
+fn total_winnings(puzzle: &Puzzle, strengths: &Strengths) -> u64 {
+    let mut ranked = puzzle
+        .lines
+        .iter()
+        .map(|line| {
+            let cards = line.hand.cards.map(|card| {
+                if strengths.is_wild(card) {
+                    Card::Joker
+                } else {
+                    card
+                }
+            });
+            let card_strengths = line.hand.cards.map(|card| strengths.strength(card));
+            (classify_joker(cards), card_strengths, line.bid)
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_unstable();
+    std::iter::zip(1u64.., ranked)
+        .map(|(i, (_, _, bid))| bid * i)
         .sum()
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
-    std::iter::zip(1u64.., puzzle.joker_lines.iter())
-        .map(|(i, line)| line.bid * i)
-        .sum()
+fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+    Ok(total_winnings(puzzle, &standard_strengths()?))
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+    Ok(total_winnings(puzzle, &joker_strengths()?))
+}
+
+struct Day07;
 
-    let puzzle = time!(parse(&input)?);
+impl Solution for Day07 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part1(puzzle)
+    }
+
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part2(puzzle)
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day07>()
 }
 
 #[cfg(test)]
@@ -240,11 +281,58 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(6440, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(6440, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
-        assert_eq!(5905, part2(&parse(SAMPLE).unwrap()));
+        assert_eq!(5905, part2(&parse(SAMPLE).unwrap()).unwrap());
+    }
+
+    fn joker_cards(hand: &str) -> [Card; 5] {
+        hand.parse::<Hand>().unwrap().cards.map(
+            |card| {
+                if card == Card::J {
+                    Card::Joker
+                } else {
+                    card
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn joker_ranks() {
+        assert_eq!(Rank::FiveOfAKind, classify_joker(joker_cards("JJJJJ")));
+        assert_eq!(Rank::FiveOfAKind, classify_joker(joker_cards("JJJJ2")));
+        assert_eq!(Rank::FourOfAKind, classify_joker(joker_cards("T55J5")));
+        assert_eq!(Rank::FourOfAKind, classify_joker(joker_cards("KTJJT")));
+        assert_eq!(Rank::FullHouse, classify_joker(joker_cards("KKJQQ")));
+        assert_eq!(Rank::OnePair, classify_joker(joker_cards("2345J")));
+        assert_eq!(Rank::Triple, classify_joker(joker_cards("2JJ34")));
+        assert_eq!(Rank::TwoPair, classify_joker(joker_cards("KK677")));
+    }
+
+    #[test]
+    fn inverted_strengths() {
+        let inverted = Strengths::new(
+            std::iter::zip("AKQJT98765432".chars(), 2u8..).collect(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(6833, total_winnings(&parse(SAMPLE).unwrap(), &inverted));
+    }
+
+    #[test]
+    fn incomplete_strengths() {
+        let missing_ace = std::iter::zip("23456789TJQK".chars(), 2u8..).collect();
+        let error = Strengths::new(missing_ace, None).unwrap_err();
+        assert!(
+            error.to_string().contains("no strength for card 'A'"),
+            "{error}"
+        );
+        let all = std::iter::zip(LABELS.chars(), 2u8..).collect();
+        let error = Strengths::new(all, Some('X')).unwrap_err();
+        assert!(error.to_string().contains("wild card 'X'"), "{error}");
     }
 }
@@ -12,10 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::time;
+use aoc_2023::math::checked_lcm_all;
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
-use std::collections::HashMap;
-use std::io::{self, Read};
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
 enum Dir {
@@ -81,55 +81,71 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn part1(puzzle: &Puzzle) -> u64 {
-    let mut current = "AAA";
-    for (step, dir) in std::iter::zip(1u64.., puzzle.directions.iter().cycle()) {
-        let node = puzzle.nodes.get(current).unwrap();
+// Counts the steps from `start` until a node matching `is_end` is reached. Returns None if a node
+// is missing, or if the walk returns to the same node at the same point in the directions without
+// having reached an end, since it would then loop forever.
+fn steps_until(puzzle: &Puzzle, start: &str, is_end: impl Fn(&str) -> bool) -> Option<u64> {
+    let mut seen = HashSet::new();
+    let mut current = start;
+    for (step, (dir_idx, dir)) in
+        std::iter::zip(1u64.., puzzle.directions.iter().enumerate().cycle())
+    {
+        if !seen.insert((current, dir_idx)) {
+            return None;
+        }
+        let node = puzzle.nodes.get(current)?;
         current = match dir {
             Dir::Left => &node.left,
             Dir::Right => &node.right,
         };
-        if current == "ZZZ" {
-            return step;
+        if is_end(current) {
+            return Some(step);
         }
     }
-    0
+    None
+}
+
+fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+    steps_until(puzzle, "AAA", |node| node == "ZZZ").ok_or_else(|| oops!("no path from AAA to ZZZ"))
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
-    puzzle
+fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+    let cycle_lengths = puzzle
         .nodes
         .keys()
         .filter(|key| key.ends_with('A'))
         // Determine the number of steps for each cycle, assuming that the initial
         // journey provides the cycle length.
-        .map(|mut current| {
-            for (step, dir) in std::iter::zip(1u64.., puzzle.directions.iter().cycle()) {
-                let node = puzzle.nodes.get(current).unwrap();
-                current = match dir {
-                    Dir::Left => &node.left,
-                    Dir::Right => &node.right,
-                };
-                if current.ends_with('Z') {
-                    return step;
-                }
-            }
-            0
+        .map(|start| {
+            steps_until(puzzle, start, |node| node.ends_with('Z'))
+                .ok_or_else(|| oops!("no path from {start} to a node ending in Z"))
         })
-        .fold(1, aoc_2023::math::lcm)
+        .collect::<Result<Vec<_>, _>>()?;
+    checked_lcm_all(cycle_lengths).ok_or_else(|| oops!("cycle length lcm overflows u64"))
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day08;
 
-    let puzzle = time!(parse(&input)?);
+impl Solution for Day08 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part1(puzzle)
+    }
 
-    Ok(())
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part2(puzzle)
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day08>()
 }
 
 #[cfg(test)]
@@ -159,11 +175,71 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(6, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(6, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
-        assert_eq!(6, part2(&parse(SAMPLE2).unwrap()));
+        assert_eq!(6, part2(&parse(SAMPLE2).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn unreachable_end() {
+        const SAMPLE: &str = concat!(
+            "LR\n", //
+            "\n",
+            "AAA = (BBB, CCC)\n",
+            "BBB = (AAA, AAA)\n",
+            "CCC = (ZZZ, AAA)\n",
+            "ZZZ = (ZZZ, ZZZ)\n",
+        );
+        let error = part1(&parse(SAMPLE).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("no path from AAA"), "{error}");
+        assert!(part2(&parse(SAMPLE).unwrap()).is_err());
+    }
+
+    #[test]
+    fn missing_node() {
+        let puzzle = parse("L\n\nAAA = (BBB, BBB)\n").unwrap();
+        assert!(part1(&puzzle).is_err());
+    }
+
+    #[test]
+    fn custom_predicate() {
+        let puzzle = parse(SAMPLE2).unwrap();
+        assert_eq!(Some(2), steps_until(&puzzle, "22A", |node| node == "22C"));
+        assert_eq!(
+            Some(1),
+            steps_until(&puzzle, "11Z", |node| node.starts_with("11"))
+        );
+        assert_eq!(None, steps_until(&puzzle, "11A", |node| node == "22Z"));
+    }
+
+    // A ghost starting at "{n}A" that first reaches "{n}Z" after n steps, and then every n steps
+    // after that.
+    fn ghost(n: u64) -> String {
+        let mut lines = vec![format!("{n}A = ({n}N1, {n}N1)")];
+        for i in 1..n - 1 {
+            lines.push(format!("{n}N{i} = ({n}N{next}, {n}N{next})", next = i + 1));
+        }
+        lines.push(format!("{n}N{} = ({n}Z, {n}Z)", n - 1));
+        lines.push(format!("{n}Z = ({n}N1, {n}N1)"));
+        lines.join("\n")
+    }
+
+    #[test]
+    fn many_ghosts() {
+        let cycle_lengths = [2, 3, 5, 7, 4];
+        let input = format!("L\n\n{}\n", cycle_lengths.map(ghost).join("\n"));
+        assert_eq!(420, part2(&parse(&input).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn cycle_lcm_overflow() {
+        // The product of the primes up to 53 is about 3.3e19, past u64::MAX.
+        let cycle_lengths = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53];
+        let input = format!("L\n\n{}\n", cycle_lengths.map(ghost).join("\n"));
+        let error = part2(&parse(&input).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("overflows"), "{error}");
     }
 }
@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::oops::Oops;
-use aoc_2023::time;
-use std::io::{self, Read};
+use aoc_2023::io_util::flag;
+use aoc_2023::timing::{run_timed, Solution};
+use aoc_2023::{oops, oops::Oops};
 use std::str::FromStr;
 
 struct Puzzle {
@@ -43,55 +43,102 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn solve<'s, Seq>(initial_seq: Seq) -> i64
-where
-    Seq: std::iter::Iterator<Item = &'s i64>,
-{
-    let mut accum = vec![initial_seq.copied().collect::<Vec<_>>()];
-    for i in 0..accum[0].len() - 1 {
+// Parses input where each sequence is a column rather than a row.
+fn parse_columns(input: &str) -> Result<Puzzle, Oops> {
+    let rows = parse(input)?;
+    let width = rows.values.first().map_or(0, Vec::len);
+    if rows.values.iter().any(|row| row.len() != width) {
+        return Err(oops!("columns have differing lengths"));
+    }
+    Ok(Puzzle {
+        values: (0..width)
+            .map(|x| rows.values.iter().map(|row| row[x]).collect())
+            .collect(),
+    })
+}
+
+// Returns the values that would follow and precede the history. Fails for an empty history.
+// Every other history stabilizes, if only at a single remaining difference, which is treated as
+// constant.
+fn extrapolate_both(seq: &[i64]) -> Result<(i64, i64), Oops> {
+    let Some(first) = seq.first() else {
+        return Err(oops!("empty history"));
+    };
+    // A constant sequence continues with the same constant in both directions.
+    if seq.iter().all(|x| x == first) {
+        return Ok((*first, *first));
+    }
+    let mut accum = vec![seq.to_vec()];
+    for i in 0..seq.len() - 1 {
         let next_seq = std::iter::zip(accum[i].iter(), accum[i].iter().skip(1))
             .map(|(a, b)| b - a)
             .collect::<Vec<_>>();
-        let next_seq_first = next_seq.first().unwrap();
-        if next_seq.iter().skip(1).all(|x| x == next_seq_first) {
-            return accum
-                .iter()
-                .rev()
-                .fold(*next_seq_first, |diff, seq| seq.last().unwrap() + diff);
+        let next_seq_first = next_seq[0];
+        if next_seq.iter().all(|x| *x == next_seq_first) {
+            return Ok(accum.iter().rev().fold(
+                (next_seq_first, next_seq_first),
+                |(next_diff, prev_diff), seq| {
+                    (
+                        seq.last().unwrap() + next_diff,
+                        seq.first().unwrap() - prev_diff,
+                    )
+                },
+            ));
         }
         accum.push(next_seq);
     }
-    unreachable!();
+    // The last difference row above has a single value, so the loop always returns.
+    Err(oops!("history never stabilizes: {:?}", accum[0]))
 }
 
-fn part1(puzzle: &Puzzle) -> i64 {
-    puzzle.values.iter().map(|seq| solve(seq.iter())).sum()
+fn part1(puzzle: &Puzzle) -> Result<i64, Oops> {
+    puzzle
+        .values
+        .iter()
+        .map(|seq| Ok(extrapolate_both(seq)?.0))
+        .sum()
 }
 
-fn part2(puzzle: &Puzzle) -> i64 {
+fn part2(puzzle: &Puzzle) -> Result<i64, Oops> {
     puzzle
         .values
         .iter()
-        .map(|seq| solve(seq.iter().rev()))
+        .map(|seq| Ok(extrapolate_both(seq)?.1))
         .sum()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day09;
+
+impl Solution for Day09 {
+    type Puzzle = Puzzle;
+    type Answer1 = i64;
+    type Answer2 = i64;
 
-    let puzzle = time!(parse(&input)?);
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        if flag("--columns") {
+            parse_columns(input)
+        } else {
+            parse(input)
+        }
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part1(puzzle: &Puzzle) -> Result<i64, Oops> {
+        part1(puzzle)
+    }
+
+    fn part2(puzzle: &Puzzle) -> Result<i64, Oops> {
+        part2(puzzle)
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day09>()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2023::math::extrapolate;
 
     const SAMPLE: &str = concat!(
         "0 3 6 9 12 15\n", //
@@ -101,11 +148,75 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(114, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(114, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
-        assert_eq!(2, part2(&parse(SAMPLE).unwrap()));
+        assert_eq!(2, part2(&parse(SAMPLE).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn both_directions() {
+        assert_eq!((18, -3), extrapolate_both(&[0, 3, 6, 9, 12, 15]).unwrap());
+        assert_eq!(
+            (68, 5),
+            extrapolate_both(&[10, 13, 16, 21, 30, 45]).unwrap()
+        );
+    }
+
+    #[test]
+    fn lagrange_extrapolation_agrees() {
+        let puzzle = parse(SAMPLE).unwrap();
+        for seq in &puzzle.values {
+            let (next, previous) = extrapolate_both(seq).unwrap();
+            assert_eq!(next, extrapolate(seq, 1));
+            assert_eq!(
+                previous,
+                extrapolate(seq, -i64::try_from(seq.len()).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn constant_sequence() {
+        assert_eq!((5, 5), extrapolate_both(&[5, 5, 5, 5]).unwrap());
+    }
+
+    #[test]
+    fn short_histories() {
+        assert_eq!((7, 7), extrapolate_both(&[7]).unwrap());
+        assert!(extrapolate_both(&[]).is_err());
+        assert_eq!((13, -3), extrapolate_both(&[1, 5, 9]).unwrap());
+    }
+
+    #[test]
+    fn single_final_difference() {
+        assert_eq!((9, -3), extrapolate_both(&[1, 5]).unwrap());
+        assert_eq!((7, 1), extrapolate_both(&[1, 2, 4]).unwrap());
+        // The differences are 1 2 4, then 1 2, then just 1.
+        assert_eq!((15, 0), extrapolate_both(&[1, 2, 4, 8]).unwrap());
+        assert_eq!(27, part1(&parse("0 3 6 9\n1 2 4 8\n").unwrap()).unwrap());
+    }
+
+    #[test]
+    fn columns() {
+        const COLUMNS_SAMPLE: &str = concat!(
+            "0 1 10\n", //
+            "3 3 13\n",
+            "6 6 16\n",
+            "9 10 21\n",
+            "12 15 30\n",
+            "15 21 45\n",
+        );
+        let puzzle = parse_columns(COLUMNS_SAMPLE).unwrap();
+        assert_eq!(parse(SAMPLE).unwrap().values, puzzle.values);
+        assert_eq!(114, part1(&puzzle).unwrap());
+        assert_eq!(2, part2(&puzzle).unwrap());
+    }
+
+    #[test]
+    fn ragged_columns() {
+        assert!(parse_columns("1 2\n3\n").is_err());
     }
 }
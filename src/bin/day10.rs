@@ -12,14 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::geometry::{Bounds2, Point2};
-use aoc_2023::time;
+use aoc_2023::geometry::{path_to_polygon, Point2};
+use aoc_2023::grid::parse_points;
+use aoc_2023::math::{interior_points, shoelace_area};
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
 use std::collections::{HashMap, HashSet};
-use std::io::{self, Read};
 use std::str::FromStr;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Direction {
     North,
     East,
@@ -125,34 +126,36 @@ impl FromStr for Puzzle {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (mut cells, start) = (0i32..).zip(s.lines()).try_fold(
-            (HashMap::new(), None),
-            |(cells, start), (y, line)| {
-                (0i32..)
-                    .zip(line.chars())
-                    .try_fold((cells, start), |(mut cells, start), (x, c)| {
-                        let pipe = match c {
-                            '|' => Pipe::Vertical,
-                            '-' => Pipe::Horizontal,
-                            'L' => Pipe::CornerL,
-                            'J' => Pipe::CornerJ,
-                            '7' => Pipe::Corner7,
-                            'F' => Pipe::CornerF,
-                            'S' => {
-                                return if start.is_none() {
-                                    Ok((cells, Some(Point2::new(x, y))))
-                                } else {
-                                    return Err(oops!("multiple starts"));
-                                }
-                            }
-                            '.' => return Ok((cells, start)),
-                            _ => return Err(oops!("invalid character")),
-                        };
-                        cells.insert(Point2::new(x, y), pipe);
-                        Ok((cells, start))
-                    })
-            },
-        )?;
+        // Visit the cells in reading order, so errors are reported at the first offending position.
+        let mut points = parse_points(s, Some('.')).into_iter().collect::<Vec<_>>();
+        points.sort_by_key(|(p, _)| (p.y, p.x));
+
+        let mut cells = HashMap::new();
+        let mut start = None;
+        for (p, c) in points {
+            let at = |oops: Oops| {
+                oops.at(
+                    usize::try_from(p.y).unwrap() + 1,
+                    usize::try_from(p.x).unwrap() + 1,
+                )
+            };
+            let pipe = match c {
+                '|' => Pipe::Vertical,
+                '-' => Pipe::Horizontal,
+                'L' => Pipe::CornerL,
+                'J' => Pipe::CornerJ,
+                '7' => Pipe::Corner7,
+                'F' => Pipe::CornerF,
+                'S' => {
+                    if start.replace(p).is_some() {
+                        return Err(at(oops!("multiple starts")));
+                    }
+                    continue;
+                }
+                _ => return Err(at(oops!("invalid character {c}"))),
+            };
+            cells.insert(p, pipe);
+        }
         let start = start.ok_or_else(|| oops!("no start"))?;
 
         let start_directions = ALL_DIRECTIONS
@@ -166,11 +169,24 @@ impl FromStr for Puzzle {
             })
             .collect::<Vec<_>>();
 
-        if start_directions.len() != 2 {
-            return Err(oops!(
-                "expected 2 connections to start, got {}",
-                start_directions.len()
-            ));
+        // Neighbors off the edge of the grid are never in `cells`, so they can't connect.
+        match start_directions.len() {
+            2 => (),
+            0 | 1 => {
+                return Err(oops!(
+                    "start at ({}, {}) is not on a loop: only {} neighboring pipe(s) connect to it",
+                    start.x,
+                    start.y,
+                    start_directions.len()
+                ))
+            }
+            n => {
+                return Err(oops!(
+                    "start at ({}, {}) is ambiguous: {n} neighboring pipes connect to it",
+                    start.x,
+                    start.y
+                ))
+            }
         }
 
         // Note that the directions in the match will be in the same order as ALL_DIRECTIONS.
@@ -191,108 +207,102 @@ impl FromStr for Puzzle {
     }
 }
 
+impl Puzzle {
+    // The pipe under the start, as inferred from the neighboring pipes that connect to it.
+    fn start_pipe(&self) -> Pipe {
+        self.cells[&self.start]
+    }
+}
+
 fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn solve(puzzle: &Puzzle) -> (u64, HashSet<Point2>) {
-    let mut steps = 0;
-    let mut visited = HashSet::new();
-    let mut currents = vec![puzzle.start, puzzle.start];
-    visited.insert(puzzle.start);
+// Returns every point on the loop through the start, in the order they're traversed. Fails if the
+// path from the start runs into a pipe that doesn't connect back, or into a cycle that never
+// returns to the start.
+fn solve(puzzle: &Puzzle) -> Result<Vec<Point2>, Oops> {
+    let mut direction = ALL_DIRECTIONS
+        .into_iter()
+        .find(|direction| puzzle.start_pipe().has_exit(*direction))
+        .ok_or_else(|| oops!("start pipe has no exits"))?;
+    let mut path = vec![puzzle.start];
+    let mut visited = HashSet::from([puzzle.start]);
+    let mut current = puzzle.start;
     loop {
-        let nexts = currents
-            .iter()
-            .filter_map(|current| {
-                let pipe = puzzle.cells.get(current).expect("traversed to empty cell");
-                ALL_DIRECTIONS.into_iter().find_map(|direction| {
-                    let candidate = current.in_direction(direction);
-                    let Some(candidate_pipe) = puzzle.cells.get(&candidate) else {
-                        return None;
-                    };
-                    if pipe.has_exit(direction)
-                        && candidate_pipe.has_exit(direction.opposite())
-                        && !visited.contains(&candidate)
-                    {
-                        visited.insert(candidate);
-                        Some(candidate)
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect::<Vec<_>>();
-        if nexts.len() < 2 {
-            return (steps + 1, visited);
+        let next = current.in_direction(direction);
+        let next_pipe = puzzle
+            .cells
+            .get(&next)
+            .filter(|pipe| pipe.has_exit(direction.opposite()))
+            .ok_or_else(|| {
+                oops!(
+                    "pipe at ({}, {}) leads to ({}, {}), which doesn't connect back",
+                    current.x,
+                    current.y,
+                    next.x,
+                    next.y
+                )
+            })?;
+        if next == puzzle.start {
+            return Ok(path);
+        }
+        if !visited.insert(next) {
+            return Err(oops!(
+                "loop revisits ({}, {}) without returning to the start",
+                next.x,
+                next.y
+            ));
         }
-        steps += 1;
-        currents = nexts;
+        path.push(next);
+        current = next;
+        direction = ALL_DIRECTIONS
+            .into_iter()
+            .find(|candidate| *candidate != direction.opposite() && next_pipe.has_exit(*candidate))
+            .ok_or_else(|| oops!("pipe at ({}, {}) is a dead end", next.x, next.y))?;
     }
 }
 
-fn part1(puzzle: &Puzzle) -> u64 {
-    solve(puzzle).0
+fn part1(puzzle: &Puzzle) -> Result<usize, Oops> {
+    Ok(solve(puzzle)?.len() / 2)
 }
 
-fn part2(puzzle: &Puzzle) -> usize {
-    let (_, visited) = solve(puzzle);
-    let bounds = Bounds2::from_points(visited.iter());
-    (bounds.min.y..=bounds.max.y)
-        .flat_map(|y| {
-            let mut in_loop = false;
-            let mut last_direction = None;
-            let visited = &visited;
-            (bounds.min.x..=bounds.max.x).filter(move |x| {
-                let current = Point2::new(*x, y);
-                if visited.contains(&current) {
-                    let pipe = puzzle.cells.get(&current).unwrap();
-                    if pipe.has_exit(Direction::North) || pipe.has_exit(Direction::South) {
-                        if *pipe == Pipe::Vertical {
-                            in_loop = !in_loop;
-                            last_direction = None;
-                            return false;
-                        }
-                        match last_direction {
-                            None => {
-                                in_loop = !in_loop;
-                                last_direction = Some(if pipe.has_exit(Direction::North) {
-                                    Direction::North
-                                } else {
-                                    Direction::South
-                                });
-                            }
-                            Some(direction) => {
-                                if pipe.has_exit(direction) {
-                                    in_loop = !in_loop;
-                                }
-                                last_direction = None;
-                            }
-                        }
-                    }
-                    return false;
-                }
-                in_loop
-            })
-        })
-        .count()
+fn part2(puzzle: &Puzzle) -> Result<i64, Oops> {
+    let path = solve(puzzle)?;
+    Ok(interior_points(
+        shoelace_area(&path_to_polygon(&path)),
+        i64::try_from(path.len())?,
+    ))
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day10;
 
-    let puzzle = time!(parse(&input)?);
+impl Solution for Day10 {
+    type Puzzle = Puzzle;
+    type Answer1 = usize;
+    type Answer2 = i64;
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<usize, Oops> {
+        part1(puzzle)
+    }
 
-    Ok(())
+    fn part2(puzzle: &Puzzle) -> Result<i64, Oops> {
+        part2(puzzle)
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day10>()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use aoc_2023::geometry::Bounds2;
 
     const SAMPLE: &str = concat!(
         "..F7.\n", //
@@ -342,13 +352,102 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(8, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(8, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
-        assert_eq!(4, part2(&parse(SAMPLE2).unwrap()));
-        assert_eq!(8, part2(&parse(SAMPLE3).unwrap()));
-        assert_eq!(10, part2(&parse(SAMPLE4).unwrap()));
+        assert_eq!(4, part2(&parse(SAMPLE2).unwrap()).unwrap());
+        assert_eq!(8, part2(&parse(SAMPLE3).unwrap()).unwrap());
+        assert_eq!(10, part2(&parse(SAMPLE4).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn inferred_start_pipe() {
+        assert_eq!(Pipe::CornerF, parse(SAMPLE).unwrap().start_pipe());
+        assert_eq!(
+            Pipe::Vertical,
+            parse(".|.\n.S.\n.|.\n").unwrap().start_pipe()
+        );
+    }
+
+    #[test]
+    fn start_in_corner() {
+        let puzzle = parse("S7\nLJ\n").unwrap();
+        assert_eq!(Pipe::CornerF, puzzle.start_pipe());
+        assert_eq!(2, part1(&puzzle).unwrap());
+        assert_eq!(0, part2(&puzzle).unwrap());
+    }
+
+    #[test]
+    fn broken_loops() {
+        // The walk east from S reaches an F, which has no west exit.
+        let error = solve(&parse("S-F7\n|.LJ\n").unwrap()).unwrap_err();
+        assert!(
+            error.to_string().contains("doesn't connect back"),
+            "{error}"
+        );
+        // The 7 leads south off the pipes.
+        let error = solve(&parse("S7\n|.\n").unwrap()).unwrap_err();
+        assert!(
+            error.to_string().contains("doesn't connect back"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn start_not_on_loop() {
+        let error = parse("S-\n..\n").err().unwrap();
+        assert!(error.to_string().contains("not on a loop"), "{error}");
+        let error = parse(".|.\n-S-\n.|.\n").err().unwrap();
+        assert!(error.to_string().contains("ambiguous"), "{error}");
+    }
+
+    #[test]
+    fn invalid_character_location() {
+        let error = parse(concat!(
+            ".....\n", //
+            ".S-7.\n", //
+            ".|X|.\n", //
+            ".L-J.\n", //
+        ))
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("line 3, column 3"), "{error}");
+    }
+
+    // Even-odd rule: casting a ray west to east along each row, a cell is enclosed if the ray has
+    // crossed the loop an odd number of times. Counting only loop pipes with a north exit treats
+    // L-7 and F-J runs as one crossing and L-J and F-7 runs as zero or two.
+    fn ray_cast_interior(puzzle: &Puzzle) -> i64 {
+        let path = solve(puzzle).unwrap().into_iter().collect::<HashSet<_>>();
+        let bounds = Bounds2::from_points(path.iter());
+        let mut interior = 0;
+        for y in bounds.min.y..=bounds.max.y {
+            let mut crossings = 0;
+            for x in bounds.min.x..=bounds.max.x {
+                let p = Point2::new(x, y);
+                if path.contains(&p) {
+                    if puzzle.cells[&p].has_exit(Direction::North) {
+                        crossings += 1;
+                    }
+                } else if crossings % 2 == 1 {
+                    interior += 1;
+                }
+            }
+        }
+        interior
+    }
+
+    #[test]
+    fn ray_cast_matches_part2() {
+        for sample in [SAMPLE, SAMPLE2, SAMPLE3, SAMPLE4] {
+            let puzzle = parse(sample).unwrap();
+            assert_eq!(
+                ray_cast_interior(&puzzle),
+                part2(&puzzle).unwrap(),
+                "{sample}"
+            );
+        }
     }
 }
@@ -13,10 +13,10 @@
 // limitations under the License.
 
 use aoc_2023::geometry::Point2;
-use aoc_2023::oops::Oops;
-use aoc_2023::time;
+use aoc_2023::math::pairwise_distance_sum;
+use aoc_2023::timing::{run_timed, Solution};
+use aoc_2023::{oops, oops::Oops};
 use std::collections::{BTreeMap, BTreeSet};
-use std::io::{self, Read};
 use std::str::FromStr;
 
 #[derive(Debug)]
@@ -32,8 +32,12 @@ impl FromStr for Puzzle {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut height = 0;
+        let mut width = 0;
         let galaxies = std::iter::zip(0i32.., s.lines())
-            .inspect(|(y, _)| height = std::cmp::max(height, *y))
+            .inspect(|(y, line)| {
+                height = std::cmp::max(height, *y + 1);
+                width = std::cmp::max(width, line.len());
+            })
             .flat_map(|(y, line)| {
                 std::iter::zip(0i32.., line.chars()).filter_map(move |(x, c)| {
                     if c == '#' {
@@ -45,8 +49,7 @@ impl FromStr for Puzzle {
             })
             .collect::<Vec<_>>();
 
-        let height = height + 1;
-        let width = height;
+        let width = i32::try_from(width)?;
 
         // (-1, -1) should never be a valid coordinate, but removes an edge case when looking up
         // how many adjustments are needed later.
@@ -73,53 +76,61 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn adjust_point_for_expansion_factor(puzzle: &Puzzle, point: Point2, factor: i32) -> Point2 {
+// Returns the (x, y) position of `point` after each empty row and column grows by `growth`. The
+// result is i64 since large factors can push coordinates well past i32::MAX.
+fn adjust_point_for_growth(puzzle: &Puzzle, point: Point2, growth: i64) -> (i64, i64) {
     let x_adj = puzzle.empty_cols.range(-1..=point.x).next_back().unwrap().1;
     let y_adj = puzzle.empty_rows.range(-1..=point.y).next_back().unwrap().1;
-    Point2::new(
-        point.x + x_adj * (factor - 1),
-        point.y + y_adj * (factor - 1),
+    (
+        i64::from(point.x) + i64::from(*x_adj) * growth,
+        i64::from(point.y) + i64::from(*y_adj) * growth,
     )
 }
 
-fn solve_with_expansion_factor(puzzle: &Puzzle, factor: i32) -> u64 {
-    let memoized = puzzle
+fn solve_with_expansion_factor(puzzle: &Puzzle, factor: u64) -> Result<u64, Oops> {
+    // An empty row replaced by `factor` rows grows by `factor - 1`; a factor of 0 would shrink it.
+    let growth = factor
+        .checked_sub(1)
+        .and_then(|growth| i64::try_from(growth).ok())
+        .ok_or_else(|| oops!("invalid expansion factor {factor}"))?;
+    let (xs, ys) = puzzle
         .galaxies
         .iter()
-        .map(|galaxy| adjust_point_for_expansion_factor(puzzle, *galaxy, factor))
-        .collect::<Vec<_>>();
-    (0..memoized.len())
-        .flat_map(|i| {
-            let memoized = &memoized;
-            (i + 1..memoized.len())
-                .map(move |j| {
-                    let src = &memoized[i];
-                    let dst = &memoized[j];
-                    Point2::manhattan_distance(src, dst)
-                })
-                .map(u64::from)
-        })
-        .sum()
+        .map(|galaxy| adjust_point_for_growth(puzzle, *galaxy, growth))
+        .unzip();
+    Ok(pairwise_distance_sum(xs) + pairwise_distance_sum(ys))
 }
-fn part1(puzzle: &Puzzle) -> u64 {
+
+fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
     solve_with_expansion_factor(puzzle, 2)
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
+fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
     solve_with_expansion_factor(puzzle, 1_000_000)
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day11;
 
-    let puzzle = time!(parse(&input)?);
+impl Solution for Day11 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
 
-    Ok(())
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part1(puzzle)
+    }
+
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        part2(puzzle)
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day11>()
 }
 
 #[cfg(test)]
@@ -141,18 +152,55 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(374, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(374, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
         assert_eq!(
             1030,
-            solve_with_expansion_factor(&parse(SAMPLE).unwrap(), 10)
+            solve_with_expansion_factor(&parse(SAMPLE).unwrap(), 10).unwrap()
         );
         assert_eq!(
             8410,
-            solve_with_expansion_factor(&parse(SAMPLE).unwrap(), 100)
+            solve_with_expansion_factor(&parse(SAMPLE).unwrap(), 100).unwrap()
+        );
+    }
+
+    #[test]
+    fn huge_expansion_factor() {
+        // Each unit of expansion adds 82 to the 292 at factor 1.
+        assert_eq!(
+            292 + 82 * (10_000_000_000 - 1),
+            solve_with_expansion_factor(&parse(SAMPLE).unwrap(), 10_000_000_000).unwrap()
+        );
+    }
+
+    #[test]
+    fn wider_than_tall() {
+        // Columns 1-4 and 6-9 and row 1 are empty, so the galaxies expand to (0, 0), (18, 0) and
+        // (9, 3).
+        let puzzle = parse(concat!(
+            "#.........#\n", //
+            "...........\n",
+            ".....#.....\n",
+        ))
+        .unwrap();
+        assert_eq!(8, puzzle.empty_cols.len() - 1);
+        assert_eq!(42, part1(&puzzle).unwrap());
+    }
+
+    #[test]
+    fn zero_expansion_factor() {
+        let error = solve_with_expansion_factor(&parse(SAMPLE).unwrap(), 0).unwrap_err();
+        assert!(
+            error.to_string().contains("invalid expansion factor 0"),
+            "{error}"
+        );
+        // A factor of 1 leaves the image unchanged.
+        assert_eq!(
+            292,
+            solve_with_expansion_factor(&parse(SAMPLE).unwrap(), 1).unwrap()
         );
     }
 }
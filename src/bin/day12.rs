@@ -13,9 +13,8 @@
 // limitations under the License.
 
 use aoc_2023::oops::Oops;
-use aoc_2023::time;
+use aoc_2023::timing::{run_timed, Solution};
 use std::collections::HashMap;
-use std::io::{self, Read};
 use std::str::FromStr;
 
 #[derive(Eq, Hash, PartialEq)]
@@ -91,10 +90,7 @@ fn recursive_solve(
 ) -> u64 {
     if records.is_empty() {
         // If there are any more broken springs, this subsequence cannot match.
-        if springs.as_bytes()[springs_matched..]
-            .iter()
-            .any(|c| *c == b'#')
-        {
+        if springs.as_bytes()[springs_matched..].contains(&b'#') {
             return 0;
         }
         return 1;
@@ -110,10 +106,7 @@ fn recursive_solve(
         // Try to find a position to slot the next group. A group can be slotted iff:
         // - the subsequence for the group contains only #s and ?s
         // - the element after the subsequence for the group is either EOL or '.' or '?'
-        if springs.as_bytes()[i..i + next_group_size]
-            .iter()
-            .any(|c| *c == b'.')
-        {
+        if springs.as_bytes()[i..i + next_group_size].contains(&b'.') {
             continue;
         }
         match springs.as_bytes().get(i + next_group_size) {
@@ -177,47 +170,49 @@ fn recursive_solve(
     count
 }
 
+fn count_arrangements(springs: &str, records: &[usize]) -> u64 {
+    let unknowns = springs
+        .chars()
+        .enumerate()
+        .filter_map(|(i, c)| if c == '?' { Some(i) } else { None })
+        .collect::<Vec<_>>();
+    recursive_solve(&mut HashMap::new(), &unknowns, records, springs, 0)
+}
+
 fn part1(puzzle: &Puzzle) -> u64 {
     std::iter::zip(puzzle.recordses.iter(), puzzle.springses.iter())
-        .map(|(records, springs)| {
-            let unknowns = springs
-                .chars()
-                .enumerate()
-                .filter_map(|(i, c)| if c == '?' { Some(i) } else { None })
-                .collect::<Vec<_>>();
-            println!("trying {springs} with {records:?}");
-            recursive_solve(&mut HashMap::new(), &unknowns, records, springs, 0)
-        })
-        .inspect(|val| println!("{val}"))
+        .map(|(records, springs)| count_arrangements(springs, records))
         .sum()
 }
 
 fn part2(puzzle: &Puzzle) -> u64 {
     std::iter::zip(puzzle.recordses5.iter(), puzzle.springses5.iter())
-        .map(|(records, springs)| {
-            let unknowns = springs
-                .chars()
-                .enumerate()
-                .filter_map(|(i, c)| if c == '?' { Some(i) } else { None })
-                .collect::<Vec<_>>();
-            println!("trying {springs} with {records:?}");
-            recursive_solve(&mut HashMap::new(), &unknowns, records, springs, 0)
-        })
-        .inspect(|val| println!("{val}"))
+        .map(|(records, springs)| count_arrangements(springs, records))
         .sum()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+struct Day12;
+
+impl Solution for Day12 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
 
-    let puzzle = time!(parse(&input)?);
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part1(puzzle))
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part2(puzzle))
+    }
+}
 
-    Ok(())
+fn main() -> Result<(), Oops> {
+    run_timed::<Day12>()
 }
 
 #[cfg(test)]
@@ -242,4 +237,87 @@ mod tests {
     fn example2() {
         assert_eq!(525152, part2(&parse(SAMPLE).unwrap()));
     }
+
+    // Every concrete assignment of the unknown springs that matches `records`. This enumerates all
+    // 2^n assignments, so it returns None for rows with too many unknowns to enumerate in
+    // reasonable time.
+    fn enumerate_arrangements(springs: &[u8], records: &[usize]) -> Option<Vec<Vec<u8>>> {
+        const MAX_UNKNOWNS: usize = 20;
+        let unknowns = (0..springs.len())
+            .filter(|i| springs[*i] == b'?')
+            .collect::<Vec<_>>();
+        if unknowns.len() > MAX_UNKNOWNS {
+            return None;
+        }
+        let arrangements = (0u32..1 << unknowns.len())
+            .filter_map(|mask| {
+                let mut arrangement = springs.to_vec();
+                for (bit, idx) in unknowns.iter().enumerate() {
+                    arrangement[*idx] = if mask & (1 << bit) != 0 { b'#' } else { b'.' };
+                }
+                let groups = arrangement
+                    .split(|c| *c == b'.')
+                    .filter(|group| !group.is_empty())
+                    .map(<[u8]>::len)
+                    .collect::<Vec<_>>();
+                if groups == records {
+                    Some(arrangement)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Some(arrangements)
+    }
+
+    #[test]
+    fn brute_force_matches_solver() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let mut checked = 0;
+        for (records, springs) in std::iter::zip(puzzle.recordses.iter(), puzzle.springses.iter()) {
+            // Also check each line unfolded twice, when that is still small enough to enumerate.
+            let unfolded_springs = [springs.as_str(); 2].join("?");
+            let unfolded_records = [records.as_slice(); 2].concat();
+            for (springs, records) in [
+                (springs.as_str(), records.as_slice()),
+                (&unfolded_springs, &unfolded_records),
+            ] {
+                let Some(arrangements) = enumerate_arrangements(springs.as_bytes(), records) else {
+                    continue;
+                };
+                assert_eq!(
+                    count_arrangements(springs, records),
+                    u64::try_from(arrangements.len()).unwrap(),
+                    "{springs} {records:?}"
+                );
+                // Each arrangement only fills in the unknowns.
+                for arrangement in arrangements {
+                    assert!(std::iter::zip(springs.bytes(), arrangement)
+                        .all(|(original, assigned)| original == b'?' || original == assigned));
+                }
+                checked += 1;
+            }
+        }
+        assert!(checked > puzzle.springses.len());
+    }
+
+    #[test]
+    fn too_many_unknowns_to_enumerate() {
+        // 32 unknowns would overflow the u32 mask; the guard refuses long before that.
+        assert_eq!(None, enumerate_arrangements(&[b'?'; 32], &[1]));
+        assert_eq!(None, enumerate_arrangements(&[b'?'; 21], &[1]));
+        assert_eq!(
+            Some(vec![b"#.".to_vec(), b".#".to_vec()]),
+            enumerate_arrangements(b"??", &[1])
+        );
+    }
+
+    #[test]
+    fn per_line_counts() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let counts = std::iter::zip(puzzle.recordses.iter(), puzzle.springses.iter())
+            .map(|(records, springs)| count_arrangements(springs, records))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1, 4, 1, 1, 4, 10], counts);
+    }
 }
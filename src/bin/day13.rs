@@ -12,43 +12,63 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::time;
+use aoc_2023::bits::{diff_bits, row_to_u64};
+use aoc_2023::io_util::verbose;
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
-use std::io::{self, Read};
 use std::str::FromStr;
 
+// A valley is stored twice, as rows and as columns, with every row or column packed into a
+// bitmask of rocks.
 #[derive(Debug)]
-struct Puzzle {
-    horizontal_valleys: Vec<Vec<String>>,
-    vertical_valleys: Vec<Vec<String>>,
+struct Valley {
+    rows: Vec<u64>,
+    cols: Vec<u64>,
 }
 
-impl FromStr for Puzzle {
+impl FromStr for Valley {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let horizontal_valleys: Vec<Vec<_>> = s
-            .split("\n\n")
-            .map(|block| block.lines().map(str::to_string).collect())
-            .collect();
-        let vertical_valleys = horizontal_valleys
-            .iter()
-            .map(|valley| {
-                let rows = valley.len();
-                let cols = valley[0].len();
-                (0..cols)
-                    .map(|col| {
-                        (0..rows)
-                            .map(|row| char::from(valley[row].as_bytes()[col]))
-                            .collect()
+        let cells: Vec<Vec<bool>> = s
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| match c {
+                        '#' => Ok(true),
+                        '.' => Ok(false),
+                        _ => Err(oops!("invalid character {c}")),
                     })
                     .collect()
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
+        let cols = cells.first().ok_or_else(|| oops!("empty valley"))?.len();
+        if cells.iter().any(|row| row.len() != cols) {
+            return Err(oops!("valley rows have different lengths"));
+        }
+        if cols > 64 || cells.len() > 64 {
+            return Err(oops!("valleys are limited to 64 rows and columns"));
+        }
+        Ok(Valley {
+            rows: cells.iter().map(|row| row_to_u64(row)).collect(),
+            cols: (0..cols)
+                .map(|col| row_to_u64(&cells.iter().map(|row| row[col]).collect::<Vec<_>>()))
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Puzzle {
+    valleys: Vec<Valley>,
+}
 
+impl FromStr for Puzzle {
+    type Err = Oops;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Puzzle {
-            horizontal_valleys,
-            vertical_valleys,
+            valleys: s.split("\n\n").map(str::parse).collect::<Result<_, _>>()?,
         })
     }
 }
@@ -57,84 +77,111 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-fn reflects(valley: &[String]) -> Option<usize> {
-    (1..valley.len()).find(|i| {
-        (0..*i).all(|j| match (valley.get(i - j - 1), valley.get(i + j)) {
-            (Some(x), Some(y)) if x == y => true,
-            (Some(_), None) | (None, Some(_)) | (None, None) => true,
-            (Some(_), Some(_)) => false,
-        })
-    })
-}
-
-fn almost_reflects(valley: &[String]) -> Option<usize> {
-    (1..valley.len()).find(|i| {
-        (0..*i)
-            .try_fold(false, |found_almost_pair, j| {
-                match (valley.get(i - j - 1), valley.get(i + j)) {
-                    (Some(x), Some(y)) if x == y => Ok(found_almost_pair),
-                    (Some(_), None) | (None, Some(_)) | (None, None) => Ok(found_almost_pair),
-                    (Some(x), Some(y)) => {
-                        if !found_almost_pair
-                            && std::iter::zip(x.chars(), y.chars())
-                                .filter(|(x, y)| x != y)
-                                .count()
-                                == 1
-                        {
-                            Ok(true)
-                        } else {
-                            Err(oops!("not this one"))
-                        }
-                    }
-                }
-            })
-            .unwrap_or(false)
-    })
-}
-
-fn part1(puzzle: &Puzzle) -> usize {
-    std::iter::zip(
-        puzzle.horizontal_valleys.iter(),
-        puzzle.vertical_valleys.iter(),
-    )
-    .map(
-        |(horizontal, vertical)| match (reflects(horizontal), reflects(vertical)) {
-            (Some(rows), None) => rows * 100,
-            (None, Some(cols)) => cols,
-            _ => unreachable!(),
-        },
-    )
-    .sum()
-}
-
-fn part2(puzzle: &Puzzle) -> usize {
-    std::iter::zip(
-        puzzle.horizontal_valleys.iter(),
-        puzzle.vertical_valleys.iter(),
-    )
-    .map(
-        |(horizontal, vertical)| match (almost_reflects(horizontal), almost_reflects(vertical)) {
-            (Some(rows), None) => rows * 100,
-            (None, Some(cols)) => cols,
-            _ => unreachable!(),
-        },
-    )
-    .sum()
+// Where a valley's mirror lies: a horizontal mirror below the given number of rows, or a vertical
+// mirror to the right of the given number of columns.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Reflection {
+    Horizontal(usize),
+    Vertical(usize),
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+impl Reflection {
+    fn summary(self) -> usize {
+        match self {
+            Reflection::Horizontal(rows) => rows * 100,
+            Reflection::Vertical(cols) => cols,
+        }
+    }
+}
 
-    let puzzle = time!(parse(&input)?);
+// The number of differing cells between the two halves when folding just before
+// line `i`.
+fn fold_differences(valley: &[u64], i: usize) -> u32 {
+    std::iter::zip(valley[..i].iter().rev(), valley[i..].iter())
+        .map(|(x, y)| diff_bits(*x, *y))
+        .sum()
+}
+
+// Finds the first line whose fold leaves exactly `smudges` cells mismatched.
+fn reflects_with_smudges(valley: &[u64], smudges: u32) -> Option<usize> {
+    (1..valley.len()).find(|i| fold_differences(valley, *i) == smudges)
+}
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+fn find_reflection_with_smudges(valley: &Valley, smudges: u32) -> Result<Reflection, Oops> {
+    match (
+        reflects_with_smudges(&valley.rows, smudges),
+        reflects_with_smudges(&valley.cols, smudges),
+    ) {
+        (Some(rows), None) => Ok(Reflection::Horizontal(rows)),
+        (None, Some(cols)) => Ok(Reflection::Vertical(cols)),
+        (None, None) => Err(oops!("no reflection with {smudges} smudge(s)")),
+        (Some(_), Some(_)) => Err(oops!(
+            "both horizontal and vertical reflections with {smudges} smudge(s)"
+        )),
+    }
+}
+
+// The reflection in each valley, in input order.
+fn reflections(puzzle: &Puzzle, smudges: u32) -> Result<Vec<Reflection>, Oops> {
+    puzzle
+        .valleys
+        .iter()
+        .map(|valley| find_reflection_with_smudges(valley, smudges))
+        .collect()
+}
+
+fn summarize(puzzle: &Puzzle, smudges: u32) -> Result<usize, Oops> {
+    Ok(reflections(puzzle, smudges)?
+        .into_iter()
+        .map(Reflection::summary)
+        .sum())
+}
 
+fn print_reflections(puzzle: &Puzzle, smudges: u32) -> Result<(), Oops> {
+    for reflection in reflections(puzzle, smudges)? {
+        println!("{reflection:?}");
+    }
     Ok(())
 }
 
+fn part1(puzzle: &Puzzle) -> Result<usize, Oops> {
+    summarize(puzzle, 0)
+}
+
+fn part2(puzzle: &Puzzle) -> Result<usize, Oops> {
+    summarize(puzzle, 1)
+}
+
+struct Day13;
+
+impl Solution for Day13 {
+    type Puzzle = Puzzle;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<usize, Oops> {
+        if verbose() {
+            print_reflections(puzzle, 0)?;
+        }
+        part1(puzzle)
+    }
+
+    fn part2(puzzle: &Puzzle) -> Result<usize, Oops> {
+        if verbose() {
+            print_reflections(puzzle, 1)?;
+        }
+        part2(puzzle)
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day13>()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,11 +206,42 @@ mod tests {
 
     #[test]
     fn example1() {
-        assert_eq!(405, part1(&parse(SAMPLE).unwrap()));
+        assert_eq!(405, part1(&parse(SAMPLE).unwrap()).unwrap());
     }
 
     #[test]
     fn example2() {
-        assert_eq!(400, part2(&parse(SAMPLE).unwrap()));
+        assert_eq!(400, part2(&parse(SAMPLE).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn smudge_counts() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(405, summarize(&puzzle, 0).unwrap());
+        assert_eq!(400, summarize(&puzzle, 1).unwrap());
+        // Folding between the two rows mismatches both columns.
+        let valley = &parse("##\n..\n").unwrap().valleys[0].rows;
+        assert_eq!(None, reflects_with_smudges(valley, 0));
+        assert_eq!(Some(1), reflects_with_smudges(valley, 2));
+        assert!(summarize(&puzzle, 100).is_err());
+    }
+
+    #[test]
+    fn reflection_axes() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(
+            vec![Reflection::Vertical(5), Reflection::Horizontal(4)],
+            reflections(&puzzle, 0).unwrap()
+        );
+        assert_eq!(
+            vec![Reflection::Horizontal(3), Reflection::Horizontal(1)],
+            reflections(&puzzle, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn too_wide() {
+        let valley = format!("{}\n{}\n", "#".repeat(65), ".".repeat(65));
+        assert!(parse(&valley).is_err());
     }
 }
@@ -12,13 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use aoc_2023::geometry::Direction;
+use aoc_2023::grid::parse_matrix;
 use aoc_2023::matrix::Matrix;
-use aoc_2023::time;
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
-use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
-use std::io::{self, Read};
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
@@ -37,13 +37,15 @@ impl FromStr for Puzzle {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let rows = s.lines().count();
-        let cols = s.lines().next().ok_or_else(|| oops!("no lines!"))?.len();
+        let chars = parse_matrix(s)?;
+        if chars.height() == 0 {
+            return Err(oops!("no lines!"));
+        }
 
-        let mut platform = Matrix::new(cols, rows, Cell::Nothing);
+        let mut platform = Matrix::new(chars.width(), chars.height(), Cell::Nothing);
 
-        for (y, line) in s.lines().enumerate() {
-            for (x, c) in line.chars().enumerate() {
+        for y in 0..chars.height() {
+            for (x, c) in chars.row(y).enumerate() {
                 platform.set(
                     x,
                     y,
@@ -51,7 +53,7 @@ impl FromStr for Puzzle {
                         'O' => Cell::Round,
                         '#' => Cell::Cube,
                         '.' => continue,
-                        _ => unreachable!(),
+                        _ => return Err(oops!("invalid character {c}")),
                     },
                 );
             }
@@ -61,94 +63,74 @@ impl FromStr for Puzzle {
     }
 }
 
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        for y in 0..self.platform.height() {
+            for cell in self.platform.row(y) {
+                write!(
+                    f,
+                    "{}",
+                    match cell {
+                        Cell::Round => 'O',
+                        Cell::Cube => '#',
+                        Cell::Nothing => '.',
+                    }
+                )?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
 impl Puzzle {
-    fn tilt_north(&mut self) {
-        for x in 0..self.platform.width() {
-            let mut next_write = 0;
-            for y in 0..self.platform.height() {
-                match self.platform.get(x, y) {
-                    Cell::Nothing => {
-                        continue;
-                    }
-                    Cell::Cube => {
-                        next_write = y + 1;
-                        continue;
-                    }
-                    Cell::Round => {
-                        self.platform.swap(x, y, x, next_write);
-                        next_write += 1;
-                    }
-                }
-            }
-        }
+    // One spin cycle: tilts north, then west, then south, then east.
+    fn spin(&mut self) {
+        self.tilt(Direction::Up);
+        self.tilt(Direction::Left);
+        self.tilt(Direction::Down);
+        self.tilt(Direction::Right);
     }
 
-    fn tilt_west(&mut self) {
-        for y in 0..self.platform.height() {
+    // Rolls every round rock as far as it can go in `direction`.
+    fn tilt(&mut self, direction: Direction) {
+        let (width, height) = (self.platform.width(), self.platform.height());
+        let (lanes, lane_len) = match direction {
+            Direction::Up | Direction::Down => (width, height),
+            Direction::Left | Direction::Right => (height, width),
+        };
+        // Maps the i-th cell of a lane, counting from the edge rocks roll towards, to (x, y).
+        let at = |lane: usize, i: usize| match direction {
+            Direction::Up => (lane, i),
+            Direction::Down => (lane, height - 1 - i),
+            Direction::Left => (i, lane),
+            Direction::Right => (width - 1 - i, lane),
+        };
+        for lane in 0..lanes {
             let mut next_write = 0;
-            for x in 0..self.platform.width() {
+            for i in 0..lane_len {
+                let (x, y) = at(lane, i);
                 match self.platform.get(x, y) {
                     Cell::Nothing => {
                         continue;
                     }
                     Cell::Cube => {
-                        next_write = x + 1;
+                        next_write = i + 1;
                         continue;
                     }
                     Cell::Round => {
-                        self.platform.swap(x, y, next_write, y);
+                        let (write_x, write_y) = at(lane, next_write);
+                        self.platform.swap(x, y, write_x, write_y);
                         next_write += 1;
                     }
                 }
             }
         }
     }
-
-    fn tilt_south(&mut self) {
-        for x in 0..self.platform.width() {
-            let mut next_write = self.platform.height() - 1;
-            for y in (0..self.platform.height()).rev() {
-                match self.platform.get(x, y) {
-                    Cell::Nothing => {
-                        continue;
-                    }
-                    Cell::Cube => {
-                        next_write = y.saturating_sub(1);
-                        continue;
-                    }
-                    Cell::Round => {
-                        self.platform.swap(x, y, x, next_write);
-                        next_write = next_write.saturating_sub(1);
-                    }
-                }
-            }
-        }
-    }
-
-    fn tilt_east(&mut self) {
-        for y in 0..self.platform.height() {
-            let mut next_write = self.platform.width() - 1;
-            for x in (0..self.platform.width()).rev() {
-                match self.platform.get(x, y) {
-                    Cell::Nothing => {
-                        continue;
-                    }
-                    Cell::Cube => {
-                        next_write = x.saturating_sub(1);
-                        continue;
-                    }
-                    Cell::Round => {
-                        self.platform.swap(x, y, next_write, y);
-                        next_write = next_write.saturating_sub(1);
-                    }
-                }
-            }
-        }
-    }
 }
 
 fn calculate(puzzle: &Puzzle) -> usize {
@@ -164,32 +146,32 @@ fn calculate(puzzle: &Puzzle) -> usize {
 
 fn part1(puzzle: &Puzzle) -> usize {
     let mut puzzle = puzzle.clone();
-    puzzle.tilt_north();
+    puzzle.tilt(Direction::Up);
     calculate(&puzzle)
 }
 
-fn part2(puzzle: &Puzzle) -> usize {
+// The load on the north support beams after `cycles` spin cycles. Once the platform starts
+// repeating, whole repetitions are skipped rather than simulated.
+fn load_after_cycles(puzzle: &Puzzle, cycles: u64) -> usize {
+    let cycles = usize::try_from(cycles).expect("cycle count fits in usize");
     let mut puzzle = puzzle.clone();
     let mut iteration = 0;
     let mut states_seen_map = HashMap::<u64, Vec<usize>>::new();
     let mut states_seen = vec![];
 
-    'cycle_finder: while iteration < 1_000_000_000 {
-        puzzle.tilt_north();
-        puzzle.tilt_west();
-        puzzle.tilt_south();
-        puzzle.tilt_east();
+    'cycle_finder: while iteration < cycles {
+        puzzle.spin();
 
-        let mut hasher = DefaultHasher::new();
-        puzzle.platform.hash(&mut hasher);
-        let state = hasher.finish();
+        let state = puzzle.platform.content_hash();
         states_seen.push(state);
-        let previouses = states_seen_map.entry(state).or_insert_with(|| vec![]);
+        let previouses = states_seen_map.entry(state).or_default();
 
         if let Some(cycle_len) = previouses.iter().find_map(|previous| {
             let cycle_len = iteration - *previous;
             let current_slice = states_seen.get(iteration - cycle_len..iteration);
-            let previous_slice = states_seen.get(iteration - cycle_len * 2..iteration - cycle_len);
+            let previous_slice = iteration
+                .checked_sub(cycle_len * 2)
+                .and_then(|begin| states_seen.get(begin..iteration - cycle_len));
             if current_slice == previous_slice {
                 Some(cycle_len)
             } else {
@@ -197,7 +179,7 @@ fn part2(puzzle: &Puzzle) -> usize {
             }
         }) {
             iteration += 1;
-            let remaining = 1_000_000_000 - iteration;
+            let remaining = cycles - iteration;
             iteration += (remaining / cycle_len) * cycle_len;
             break 'cycle_finder;
         }
@@ -206,28 +188,40 @@ fn part2(puzzle: &Puzzle) -> usize {
         iteration += 1;
     }
 
-    while iteration < 1_000_000_000 {
-        puzzle.tilt_north();
-        puzzle.tilt_west();
-        puzzle.tilt_south();
-        puzzle.tilt_east();
+    while iteration < cycles {
+        puzzle.spin();
         iteration += 1;
     }
 
     calculate(&puzzle)
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+fn part2(puzzle: &Puzzle) -> usize {
+    load_after_cycles(puzzle, 1_000_000_000)
+}
 
-    let puzzle = time!(parse(&input)?);
+struct Day14;
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+impl Solution for Day14 {
+    type Puzzle = Puzzle;
+    type Answer1 = usize;
+    type Answer2 = usize;
 
-    Ok(())
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<usize, Oops> {
+        Ok(part1(puzzle))
+    }
+
+    fn part2(puzzle: &Puzzle) -> Result<usize, Oops> {
+        Ok(part2(puzzle))
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day14>()
 }
 
 #[cfg(test)]
@@ -256,4 +250,63 @@ mod tests {
     fn example2() {
         assert_eq!(64, part2(&parse(SAMPLE).unwrap()));
     }
+
+    #[test]
+    fn few_cycles() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let mut simulated = puzzle.clone();
+        for cycles in 0..=20 {
+            assert_eq!(
+                calculate(&simulated),
+                load_after_cycles(&puzzle, cycles),
+                "{cycles}"
+            );
+            simulated.spin();
+        }
+    }
+
+    #[test]
+    fn render() {
+        assert_eq!(SAMPLE, parse(SAMPLE).unwrap().to_string());
+    }
+
+    #[test]
+    fn tilts() {
+        const GRID: &str = concat!(
+            ".O.#\n", //
+            "O..O\n", //
+            "#.O.\n", //
+        );
+        for (direction, expected) in [
+            (Direction::Up, "OOO#\n...O\n#...\n"),
+            (Direction::Down, "...#\nO...\n#OOO\n"),
+            (Direction::Left, "O..#\nOO..\n#O..\n"),
+            (Direction::Right, "..O#\n..OO\n#..O\n"),
+        ] {
+            let mut puzzle = parse(GRID).unwrap();
+            puzzle.tilt(direction);
+            assert_eq!(expected, puzzle.to_string(), "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn one_spin() {
+        let mut puzzle = parse(SAMPLE).unwrap();
+        puzzle.spin();
+        assert_eq!(
+            concat!(
+                ".....#....\n",
+                "....#...O#\n",
+                "...OO##...\n",
+                ".OO#......\n",
+                ".....OOO#.\n",
+                ".O#...O#.#\n",
+                "....O#....\n",
+                "......OOOO\n",
+                "#...O###..\n",
+                "#..OO#....\n",
+            ),
+            puzzle.to_string()
+        );
+    }
 }
@@ -12,9 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::time;
+use aoc_2023::io_util::verbose;
+use aoc_2023::timing::{run_timed, Solution};
 use aoc_2023::{oops, oops::Oops};
-use std::io::{self, Read};
 use std::str::FromStr;
 
 fn hash(input: &str) -> u8 {
@@ -100,10 +100,11 @@ struct Lens<'a> {
     focal_len: u8,
 }
 
-fn part2(puzzle: &Puzzle) -> u64 {
+// Runs the HASHMAP procedure, returning the contents of all 256 boxes.
+fn arrange_lenses(steps: &[ParsedStep]) -> Vec<Vec<Lens<'_>>> {
     let mut lenses_boxes = vec![Vec::<Lens>::default(); 256];
 
-    for parsed_step in &puzzle.parsed_steps {
+    for parsed_step in steps {
         let box_idx = usize::from(hash(&parsed_step.label));
         let lenses_box = &mut lenses_boxes[box_idx];
         let lens_idx = lenses_box
@@ -128,8 +129,30 @@ fn part2(puzzle: &Puzzle) -> u64 {
         }
     }
 
+    lenses_boxes
+}
+
+// Formats the non-empty boxes one per line, e.g. "Box 3: [ot 7] [ab 5] [pc 6]".
+fn render_boxes(lenses_boxes: &[Vec<Lens>]) -> String {
+    lenses_boxes
+        .iter()
+        .enumerate()
+        .filter(|(_, lenses_box)| !lenses_box.is_empty())
+        .map(|(box_idx, lenses_box)| {
+            let lenses = lenses_box
+                .iter()
+                .map(|lens| format!("[{} {}]", lens.label, lens.focal_len))
+                .collect::<Vec<_>>();
+            format!("Box {box_idx}: {}", lenses.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// The total focusing power of the lenses left in the boxes after running `steps`.
+fn focusing_power(steps: &[ParsedStep]) -> u64 {
     (0u64..)
-        .zip(lenses_boxes.iter())
+        .zip(arrange_lenses(steps).iter())
         .map(|(box_idx, lenses_box)| {
             (0u64..)
                 .zip(lenses_box.iter())
@@ -139,24 +162,42 @@ fn part2(puzzle: &Puzzle) -> u64 {
         .sum()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+fn part2(puzzle: &Puzzle) -> u64 {
+    if verbose() {
+        println!("{}", render_boxes(&arrange_lenses(&puzzle.parsed_steps)));
+    }
+    focusing_power(&puzzle.parsed_steps)
+}
+
+struct Day15;
+
+impl Solution for Day15 {
+    type Puzzle = Puzzle;
+    type Answer1 = u64;
+    type Answer2 = u64;
 
-    let puzzle = time!(parse(&input)?);
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+    fn part1(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part1(puzzle))
+    }
 
-    Ok(())
+    fn part2(puzzle: &Puzzle) -> Result<u64, Oops> {
+        Ok(part2(puzzle))
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day15>()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    const SAMPLE: &str = concat!("rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7",);
+    const SAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
 
     #[test]
     fn example1() {
@@ -168,6 +209,27 @@ mod tests {
         assert_eq!(145, part2(&parse(SAMPLE).unwrap()));
     }
 
+    #[test]
+    fn focusing_power_of_steps() {
+        let steps = SAMPLE
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<ParsedStep>, _>>()
+            .unwrap();
+        assert_eq!(145, focusing_power(&steps));
+        assert_eq!(1, focusing_power(&steps[..1]));
+        assert_eq!(0, focusing_power(&[]));
+    }
+
+    #[test]
+    fn rendered_boxes() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(
+            "Box 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]",
+            render_boxes(&arrange_lenses(&puzzle.parsed_steps))
+        );
+    }
+
     #[test]
     fn hash() {
         assert_eq!(52, super::hash("HASH"));
@@ -12,14 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use aoc_2023::geometry::{Bounds2, Point2};
-use aoc_2023::oops::Oops;
-use aoc_2023::time;
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::io::{self, Read};
+use aoc_2023::geometry::{Bounds2, Direction, Point2};
+use aoc_2023::matrix::Matrix;
+use aoc_2023::parallel::par_map;
+use aoc_2023::timing::{run_timed, Solution};
+use aoc_2023::{oops, oops::Oops};
+use std::collections::{HashSet, VecDeque};
 use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 enum Space {
     Empty,
     DiagonalMirror,
@@ -30,33 +31,57 @@ enum Space {
 
 #[derive(Debug)]
 struct Puzzle {
-    // TODO: rework Matrix so get() returns an Option.
-    spaces: HashMap<Point2, Space>,
+    spaces: Matrix<Space>,
+    // The coordinates covered by `spaces`; `bounds.min` corresponds to (0, 0) in the matrix.
+    bounds: Bounds2,
+}
+
+impl Puzzle {
+    fn space_at(&self, p: Point2) -> Option<Space> {
+        if !self.bounds.contains(&p) {
+            return None;
+        }
+        self.spaces.get_point(Point2::new(
+            p.x - self.bounds.min.x,
+            p.y - self.bounds.min.y,
+        ))
+    }
 }
 
 impl FromStr for Puzzle {
     type Err = Oops;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let spaces = (0i32..)
-            .zip(s.lines())
-            .flat_map(|(y, line)| {
-                (0i32..).zip(line.chars()).map(move |(x, c)| {
-                    (
-                        Point2::new(x, y),
-                        match c {
-                            '/' => Space::AntiDiagonalMirror,
-                            '\\' => Space::DiagonalMirror,
-                            '|' => Space::VerticalSplitter,
-                            '-' => Space::HorizontalSplitter,
-                            '.' => Space::Empty,
-                            _ => unreachable!(),
-                        },
-                    )
-                })
-            })
-            .collect();
-        Ok(Puzzle { spaces })
+        let height = s.lines().count();
+        let width = s.lines().next().ok_or_else(|| oops!("no lines!"))?.len();
+        let mut spaces = Matrix::new(width, height, Space::Empty);
+        for (y, line) in s.lines().enumerate() {
+            if line.len() != width {
+                return Err(oops!(
+                    "line {y} has length {}, expected {width}",
+                    line.len()
+                ));
+            }
+            for (x, c) in line.chars().enumerate() {
+                spaces.set(
+                    x,
+                    y,
+                    match c {
+                        '/' => Space::AntiDiagonalMirror,
+                        '\\' => Space::DiagonalMirror,
+                        '|' => Space::VerticalSplitter,
+                        '-' => Space::HorizontalSplitter,
+                        '.' => Space::Empty,
+                        _ => return Err(oops!("invalid character {c}")),
+                    },
+                );
+            }
+        }
+        let bounds = Bounds2 {
+            min: Point2::new(0, 0),
+            max: Point2::new(i32::try_from(width)? - 1, i32::try_from(height)? - 1),
+        };
+        Ok(Puzzle { spaces, bounds })
     }
 }
 
@@ -64,14 +89,6 @@ fn parse(input: &str) -> Result<Puzzle, Oops> {
     input.parse()
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
-}
-
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 struct Cursor {
     position: Point2,
@@ -84,9 +101,9 @@ struct EnergizedState {
 }
 
 impl EnergizedState {
-    fn new(initial_cursor: Cursor) -> Self {
+    fn new() -> Self {
         EnergizedState {
-            cursors: VecDeque::from([initial_cursor]),
+            cursors: VecDeque::new(),
             visited: HashSet::new(),
         }
     }
@@ -105,141 +122,199 @@ impl EnergizedState {
         }
     }
 
-    fn energized_count(&self) -> usize {
-        self.visited
-            .iter()
-            .map(|cursor| cursor.position)
-            .collect::<HashSet<_>>()
-            .len()
+    fn energized_cells(&self) -> HashSet<Point2> {
+        self.visited.iter().map(|cursor| cursor.position).collect()
     }
 }
 
-fn energize(puzzle: &Puzzle, initial_cursor: Cursor) -> usize {
-    let mut state = EnergizedState::new(initial_cursor);
+// Pushes the cursor(s) resulting from a beam travelling in `direction` entering `position`.
+fn enter<F>(space_at: &F, state: &mut EnergizedState, position: Point2, direction: Direction)
+where
+    F: Fn(Point2) -> Option<Space>,
+{
+    let Some(space) = space_at(position) else {
+        return;
+    };
+
+    match (space, direction) {
+        (Space::VerticalSplitter, Direction::Left | Direction::Right) => {
+            state.push_cursor(position, Direction::Up);
+            state.push_cursor(position, Direction::Down);
+        }
+        (Space::HorizontalSplitter, Direction::Up | Direction::Down) => {
+            state.push_cursor(position, Direction::Left);
+            state.push_cursor(position, Direction::Right);
+        }
+        (Space::DiagonalMirror, direction) => {
+            state.push_cursor(
+                position,
+                match direction {
+                    Direction::Up => Direction::Left,
+                    Direction::Right => Direction::Down,
+                    Direction::Down => Direction::Right,
+                    Direction::Left => Direction::Up,
+                },
+            );
+        }
+        (Space::AntiDiagonalMirror, direction) => {
+            state.push_cursor(
+                position,
+                match direction {
+                    Direction::Up => Direction::Right,
+                    Direction::Left => Direction::Down,
+                    Direction::Down => Direction::Left,
+                    Direction::Right => Direction::Up,
+                },
+            );
+        }
+        _ => {
+            state.push_cursor(position, direction);
+        }
+    }
+}
+
+// `initial_cursor` is the first space the beam enters and the direction it enters in. Starting on
+// the grid rather than just outside it means the beam never needs an off-grid coordinate, which
+// might not be representable for a grid at the edge of the coordinate space.
+fn energized_cells_with<F>(space_at: F, initial_cursor: Cursor) -> HashSet<Point2>
+where
+    F: Fn(Point2) -> Option<Space>,
+{
+    let mut state = EnergizedState::new();
+    enter(
+        &space_at,
+        &mut state,
+        initial_cursor.position,
+        initial_cursor.direction,
+    );
     while let Some(Cursor {
         position,
         direction,
     }) = state.next_cursor()
     {
-        let next_position = match direction {
-            Direction::Up => Point2::new(position.x, position.y - 1),
-            Direction::Right => Point2::new(position.x + 1, position.y),
-            Direction::Down => Point2::new(position.x, position.y + 1),
-            Direction::Left => Point2::new(position.x - 1, position.y),
-        };
-
-        let Some(next_space) = puzzle.spaces.get(&next_position) else {
-            continue;
-        };
-
-        match (next_space, direction) {
-            (Space::VerticalSplitter, Direction::Left | Direction::Right) => {
-                state.push_cursor(next_position, Direction::Up);
-                state.push_cursor(next_position, Direction::Down);
-            }
-            (Space::HorizontalSplitter, Direction::Up | Direction::Down) => {
-                state.push_cursor(next_position, Direction::Left);
-                state.push_cursor(next_position, Direction::Right);
-            }
-            (Space::DiagonalMirror, direction) => {
-                state.push_cursor(
-                    next_position,
-                    match direction {
-                        Direction::Up => Direction::Left,
-                        Direction::Right => Direction::Down,
-                        Direction::Down => Direction::Right,
-                        Direction::Left => Direction::Up,
-                    },
-                );
-            }
-            (Space::AntiDiagonalMirror, direction) => {
-                state.push_cursor(
-                    next_position,
-                    match direction {
-                        Direction::Up => Direction::Right,
-                        Direction::Left => Direction::Down,
-                        Direction::Down => Direction::Left,
-                        Direction::Right => Direction::Up,
-                    },
-                );
-            }
-            _ => {
-                state.push_cursor(next_position, direction);
-            }
+        if let Some(next_position) = position.checked_add(direction.delta()) {
+            enter(&space_at, &mut state, next_position, direction);
         }
     }
 
-    state.energized_count()
+    state.energized_cells()
+}
+
+fn energized_cells(puzzle: &Puzzle, initial_cursor: Cursor) -> HashSet<Point2> {
+    energized_cells_with(|p| puzzle.space_at(p), initial_cursor)
+}
+
+// Counts only the energized cells inside `region`.
+fn energize_in_region(puzzle: &Puzzle, initial_cursor: Cursor, region: &Bounds2) -> usize {
+    energized_cells(puzzle, initial_cursor)
+        .iter()
+        .filter(|p| region.contains(p))
+        .count()
+}
+
+fn energize(puzzle: &Puzzle, initial_cursor: Cursor) -> usize {
+    energize_in_region(puzzle, initial_cursor, &puzzle.bounds)
 }
 
 fn part1(puzzle: &Puzzle) -> usize {
     let initial_cursor = Cursor {
-        position: Point2::new(-1, 0),
+        position: puzzle.bounds.min,
         direction: Direction::Right,
     };
     energize(puzzle, initial_cursor)
 }
 
-fn part2(puzzle: &Puzzle) -> usize {
-    let bounds = Bounds2::from_points(puzzle.spaces.keys());
-
-    (bounds.min.y..bounds.max.y)
-        .map(|y| {
-            std::cmp::max(
-                {
-                    let initial_cursor = Cursor {
-                        position: Point2::new(bounds.min.x - 1, y),
-                        direction: Direction::Right,
-                    };
-                    energize(puzzle, initial_cursor)
+fn edge_cursors(puzzle: &Puzzle) -> Vec<Cursor> {
+    let bounds = &puzzle.bounds;
+
+    (bounds.min.y..=bounds.max.y)
+        .flat_map(|y| {
+            [
+                Cursor {
+                    position: Point2::new(bounds.min.x, y),
+                    direction: Direction::Right,
                 },
-                {
-                    let initial_cursor = Cursor {
-                        position: Point2::new(bounds.max.x + 1, y),
-                        direction: Direction::Right,
-                    };
-                    energize(puzzle, initial_cursor)
+                Cursor {
+                    position: Point2::new(bounds.max.x, y),
+                    direction: Direction::Left,
                 },
-            )
+            ]
         })
-        .chain((bounds.min.x..bounds.max.x).map(|x| {
-            std::cmp::max(
-                {
-                    let initial_cursor = Cursor {
-                        position: Point2::new(x, bounds.min.y - 1),
-                        direction: Direction::Down,
-                    };
-                    energize(puzzle, initial_cursor)
+        .chain((bounds.min.x..=bounds.max.x).flat_map(|x| {
+            [
+                Cursor {
+                    position: Point2::new(x, bounds.min.y),
+                    direction: Direction::Down,
                 },
-                {
-                    let initial_cursor = Cursor {
-                        position: Point2::new(x, bounds.max.y + 1),
-                        direction: Direction::Up,
-                    };
-                    energize(puzzle, initial_cursor)
+                Cursor {
+                    position: Point2::new(x, bounds.max.y),
+                    direction: Direction::Up,
                 },
-            )
+            ]
         }))
+        .collect()
+}
+
+fn all_edge_energizations(puzzle: &Puzzle) -> Vec<(Cursor, usize)> {
+    edge_cursors(puzzle)
+        .into_iter()
+        .map(|initial_cursor| (initial_cursor, energize(puzzle, initial_cursor)))
+        .collect()
+}
+
+fn max_energization_sequential(puzzle: &Puzzle) -> usize {
+    all_edge_energizations(puzzle)
+        .into_iter()
+        .map(|(_, count)| count)
         .max()
         .unwrap()
 }
 
-fn main() -> Result<(), Oops> {
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
-    let input = input;
+fn max_energization_parallel(puzzle: &Puzzle) -> usize {
+    par_map(&edge_cursors(puzzle), |initial_cursor| {
+        energize(puzzle, *initial_cursor)
+    })
+    .into_iter()
+    .max()
+    .unwrap()
+}
 
-    let puzzle = time!(parse(&input)?);
+fn part2(puzzle: &Puzzle) -> usize {
+    if cfg!(feature = "parallel") {
+        max_energization_parallel(puzzle)
+    } else {
+        max_energization_sequential(puzzle)
+    }
+}
 
-    println!("{}", time!(part1(&puzzle)));
-    println!("{}", time!(part2(&puzzle)));
+struct Day16;
 
-    Ok(())
+impl Solution for Day16 {
+    type Puzzle = Puzzle;
+    type Answer1 = usize;
+    type Answer2 = usize;
+
+    fn parse(input: &str) -> Result<Puzzle, Oops> {
+        parse(input)
+    }
+
+    fn part1(puzzle: &Puzzle) -> Result<usize, Oops> {
+        Ok(part1(puzzle))
+    }
+
+    fn part2(puzzle: &Puzzle) -> Result<usize, Oops> {
+        Ok(part2(puzzle))
+    }
+}
+
+fn main() -> Result<(), Oops> {
+    run_timed::<Day16>()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     const SAMPLE: &str = r".|...\....
 |.-.\.....
@@ -259,6 +334,102 @@ mod tests {
 
     #[test]
     fn example2() {
-        assert_eq!(2468013579, part2(&parse(SAMPLE).unwrap()));
+        // From the puzzle walkthrough: the best configuration enters the fourth column of the
+        // top row heading down, energizing 51 tiles.
+        let puzzle = parse(SAMPLE).unwrap();
+        let best = Cursor {
+            position: Point2::new(3, 0),
+            direction: Direction::Down,
+        };
+        assert_eq!(51, energize(&puzzle, best));
+        assert_eq!(51, part2(&puzzle));
+    }
+
+    #[test]
+    fn edge_energizations() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let energizations = all_edge_energizations(&puzzle);
+        assert_eq!(40, energizations.len());
+        assert_eq!(
+            Some(part2(&puzzle)),
+            energizations.into_iter().map(|(_, count)| count).max()
+        );
+    }
+
+    #[test]
+    fn energize_sub_region() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let initial_cursor = Cursor {
+            position: Point2::new(0, 0),
+            direction: Direction::Right,
+        };
+        // From the walkthrough, the energized cells in the top-left 3x3 are:
+        // ###
+        // .#.
+        // .#.
+        let region = Bounds2 {
+            min: Point2::new(0, 0),
+            max: Point2::new(2, 2),
+        };
+        assert_eq!(5, energize_in_region(&puzzle, initial_cursor, &region));
+        let everything = Bounds2 {
+            min: Point2::new(0, 0),
+            max: Point2::new(9, 9),
+        };
+        assert_eq!(46, energize_in_region(&puzzle, initial_cursor, &everything));
+    }
+
+    #[test]
+    fn parallel_matches_sequential() {
+        let puzzle = parse(SAMPLE).unwrap();
+        assert_eq!(51, max_energization_sequential(&puzzle));
+        assert_eq!(51, max_energization_parallel(&puzzle));
+    }
+
+    #[test]
+    fn grid_at_coordinate_limits() {
+        let mut puzzle = parse(SAMPLE).unwrap();
+        puzzle.bounds = Bounds2 {
+            min: Point2::new(i32::MAX - 9, i32::MIN),
+            max: Point2::new(i32::MAX, i32::MIN + 9),
+        };
+        assert_eq!(51, part2(&puzzle));
+    }
+
+    #[test]
+    fn matrix_matches_hash_map() {
+        let puzzle = parse(SAMPLE).unwrap();
+        let spaces = (0..10)
+            .flat_map(|y| (0..10).map(move |x| Point2::new(x, y)))
+            .map(|p| (p, puzzle.spaces.get_point(p).unwrap()))
+            .collect::<HashMap<_, _>>();
+        for (initial_cursor, _) in all_edge_energizations(&puzzle) {
+            assert_eq!(
+                energized_cells_with(|p| spaces.get(&p).copied(), initial_cursor),
+                energized_cells(&puzzle, initial_cursor)
+            );
+        }
+    }
+
+    #[test]
+    fn optimum_on_last_column() {
+        // Only a beam entering the top-right corner heading left reaches the splitter from below.
+        let puzzle = parse(concat!(
+            "..../\n", //
+            "-....\n", "\\.../\n",
+        ))
+        .unwrap();
+        let mut energizations = all_edge_energizations(&puzzle);
+        energizations.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        assert_eq!(
+            Cursor {
+                position: Point2::new(4, 0),
+                direction: Direction::Left,
+            },
+            energizations[0].0
+        );
+        assert_eq!(11, energizations[0].1);
+        assert!(energizations[1].1 < 11);
+        assert_eq!(11, part2(&puzzle));
     }
 }
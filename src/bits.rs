@@ -0,0 +1,53 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Packs a row of flags into an integer, with the first element in the most
+// significant used bit. Rows are limited to 64 columns; longer rows panic.
+#[must_use]
+pub fn row_to_u64(row: &[bool]) -> u64 {
+    assert!(row.len() <= 64, "row of {} columns exceeds 64", row.len());
+    row.iter().fold(0, |acc, &bit| (acc << 1) | u64::from(bit))
+}
+
+// The number of positions at which two encoded rows differ.
+#[must_use]
+pub fn diff_bits(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_rows() {
+        assert_eq!(0, row_to_u64(&[]));
+        assert_eq!(0b101, row_to_u64(&[true, false, true]));
+        assert_eq!(u64::MAX, row_to_u64(&[true; 64]));
+    }
+
+    #[test]
+    fn one_position_differs() {
+        let a = row_to_u64(&[true, false, true, true]);
+        let b = row_to_u64(&[true, true, true, true]);
+        assert_eq!(1, diff_bits(a, b));
+        assert_eq!(0, diff_bits(a, a));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds 64")]
+    fn too_wide() {
+        let _ = row_to_u64(&[false; 65]);
+    }
+}
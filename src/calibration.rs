@@ -0,0 +1,149 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Helpers for pulling the first and last of a set of tokens out of a string. Each token in a table
+// is paired with the digit it stands for, so different spellings can share a value.
+
+pub const DIGITS: &[(&str, u64)] = &[
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+];
+
+pub const DIGITS_AND_DIGIT_WORDS: &[(&str, u64)] = &[
+    ("0", 0),
+    ("1", 1),
+    ("2", 2),
+    ("3", 3),
+    ("4", 4),
+    ("5", 5),
+    ("6", 6),
+    ("7", 7),
+    ("8", 8),
+    ("9", 9),
+    ("zero", 0),
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+// The value of the token that starts earliest in `s`. Every position is checked against every
+// token, so tokens that share letters, like "oneight", are all found regardless of their order in
+// the table.
+#[must_use]
+pub fn first_digit(s: &str, tokens: &[(&str, u64)]) -> Option<u64> {
+    (0..s.len())
+        .filter(|i| s.is_char_boundary(*i))
+        .find_map(|i| token_at(&s[i..], tokens))
+}
+
+// The value of the token that starts latest in `s`.
+#[must_use]
+pub fn last_digit(s: &str, tokens: &[(&str, u64)]) -> Option<u64> {
+    (0..s.len())
+        .rev()
+        .filter(|i| s.is_char_boundary(*i))
+        .find_map(|i| token_at(&s[i..], tokens))
+}
+
+fn token_at(s: &str, tokens: &[(&str, u64)]) -> Option<u64> {
+    tokens
+        .iter()
+        .find(|(token, _)| s.starts_with(token))
+        .map(|(_, digit)| *digit)
+}
+
+// The two-digit number formed from the first and last tokens in `s`, or `None` if `s` contains
+// none of the tokens.
+#[must_use]
+pub fn calibration_value(s: &str, tokens: &[(&str, u64)]) -> Option<u64> {
+    Some(first_digit(s, tokens)? * 10 + last_digit(s, tokens)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_words() {
+        assert_eq!(Some(8), first_digit("eightwothree", DIGITS_AND_DIGIT_WORDS));
+        assert_eq!(Some(3), last_digit("eightwothree", DIGITS_AND_DIGIT_WORDS));
+    }
+
+    #[test]
+    fn shared_letters() {
+        for (s, expected) in [("oneight", 18), ("eightwo", 82), ("twone", 21)] {
+            assert_eq!(
+                Some(expected),
+                calibration_value(s, DIGITS_AND_DIGIT_WORDS),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn table_order_does_not_matter() {
+        let mut reversed = DIGITS_AND_DIGIT_WORDS.to_vec();
+        reversed.reverse();
+        for s in ["oneight", "eightwo", "twone"] {
+            assert_eq!(
+                first_digit(s, DIGITS_AND_DIGIT_WORDS),
+                first_digit(s, &reversed),
+                "{s}"
+            );
+            assert_eq!(
+                last_digit(s, DIGITS_AND_DIGIT_WORDS),
+                last_digit(s, &reversed),
+                "{s}"
+            );
+        }
+        assert_eq!(Some(1), first_digit("oneight", &reversed));
+        assert_eq!(Some(8), last_digit("oneight", &reversed));
+    }
+
+    #[test]
+    fn token_inside_another() {
+        // "b" lies within the earlier match of "abc", but "abc" still starts first.
+        assert_eq!(Some(0), first_digit("abc", &[("abc", 0), ("b", 1)]));
+        assert_eq!(Some(1), last_digit("abc", &[("abc", 0), ("b", 1)]));
+    }
+
+    #[test]
+    fn digits_only() {
+        assert_eq!(Some(7), first_digit("seven7two2", DIGITS));
+        assert_eq!(Some(2), last_digit("seven7two2", DIGITS));
+        assert_eq!(None, first_digit("none", DIGITS));
+    }
+
+    #[test]
+    fn custom_tokens() {
+        let tokens = &[("1", 1), ("one", 1), ("uno", 1), ("dos", 2)];
+        assert_eq!(Some(12), calibration_value("unoxxtwodos", tokens));
+        assert_eq!(Some(11), calibration_value("2one", tokens));
+        assert_eq!(None, calibration_value("two", tokens));
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::{self, HashMap};
+use std::hash::Hash;
+
+#[derive(Clone, Debug)]
+pub struct Counter<T> {
+    counts: HashMap<T, usize>,
+}
+
+impl<T> Default for Counter<T> {
+    fn default() -> Self {
+        Counter {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> Counter<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, item: T) {
+        *self.counts.entry(item).or_insert(0) += 1;
+    }
+
+    #[must_use]
+    pub fn get(&self, item: &T) -> usize {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    // The number of distinct items counted.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    // Ties are broken arbitrarily.
+    #[must_use]
+    pub fn most_common(&self) -> Option<(&T, usize)> {
+        self.counts
+            .iter()
+            .max_by_key(|(_, &count)| count)
+            .map(|(item, &count)| (item, count))
+    }
+
+    #[must_use]
+    pub fn iter(&self) -> hash_map::Iter<'_, T, usize> {
+        self.counts.iter()
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for Counter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Counter::new();
+        for item in iter {
+            counter.add(item);
+        }
+        counter
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Counter<T> {
+    type Item = (&'a T, &'a usize);
+    type IntoIter = hash_map::Iter<'a, T, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.counts.iter()
+    }
+}
+
+// The multiplicities of the distinct items, sorted in descending order, e.g. [3, 2] for a full
+// house.
+#[must_use]
+pub fn count_signature<T: Eq + Hash>(items: &[T]) -> Vec<usize> {
+    let mut counts = items
+        .iter()
+        .collect::<Counter<_>>()
+        .iter()
+        .map(|(_, &count)| count)
+        .collect::<Vec<_>>();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty() {
+        let counter = Counter::<char>::new();
+        assert!(counter.is_empty());
+        assert_eq!(0, counter.get(&'A'));
+        assert_eq!(None, counter.most_common());
+    }
+
+    #[test]
+    fn card_hand() {
+        let counter = "KTJJT".chars().collect::<Counter<_>>();
+        assert_eq!(3, counter.len());
+        assert_eq!(1, counter.get(&'K'));
+        assert_eq!(2, counter.get(&'T'));
+        assert_eq!(2, counter.get(&'J'));
+        assert_eq!(0, counter.get(&'A'));
+    }
+
+    #[test]
+    fn most_common() {
+        let counter = "QQQJA".chars().collect::<Counter<_>>();
+        assert_eq!(Some((&'Q', 3)), counter.most_common());
+    }
+
+    #[test]
+    fn signature() {
+        assert_eq!(vec![3, 2], count_signature(&['A', 'A', 'A', 'K', 'K']));
+        assert_eq!(vec![2, 1, 1, 1], count_signature(&[3, 2, 10, 3, 13]));
+        assert_eq!(
+            vec![1; 5],
+            count_signature(&"23456".chars().collect::<Vec<_>>())
+        );
+        assert!(count_signature::<u8>(&[]).is_empty());
+    }
+}
@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::math::gcd;
 use std::borrow::Borrow;
+use std::collections::HashSet;
 use std::ops::{Add, AddAssign, Sub};
 
 #[derive(Clone, Copy, Default, Debug, Eq, Hash, PartialEq)]
@@ -28,7 +30,7 @@ impl Point2 {
     }
 
     #[must_use]
-    pub fn all_neighbors(&self) -> Neighbors2 {
+    pub fn all_neighbors(&self) -> Neighbors2<'_> {
         const NEIGHBOR_VECTORS: [Vector2; 8] = [
             Vector2::new(-1, 0),
             Vector2::new(1, 0),
@@ -47,7 +49,7 @@ impl Point2 {
     }
 
     #[must_use]
-    pub fn cardinal_neighbors(&self) -> Neighbors2 {
+    pub fn cardinal_neighbors(&self) -> Neighbors2<'_> {
         const NEIGHBOR_VECTORS: [Vector2; 4] = [
             Vector2::new(-1, 0),
             Vector2::new(1, 0),
@@ -62,7 +64,7 @@ impl Point2 {
     }
 
     #[must_use]
-    pub fn diagonal_neighbors(&self) -> Neighbors2 {
+    pub fn diagonal_neighbors(&self) -> Neighbors2<'_> {
         const NEIGHBOR_VECTORS: [Vector2; 4] = [
             Vector2::new(-1, -1),
             Vector2::new(-1, 1),
@@ -76,6 +78,29 @@ impl Point2 {
         }
     }
 
+    #[must_use]
+    pub fn checked_add(self, rhs: Vector2) -> Option<Point2> {
+        Some(Point2::new(
+            self.x.checked_add(rhs.x)?,
+            self.y.checked_add(rhs.y)?,
+        ))
+    }
+
+    // Divides both components by their gcd, e.g. (4, -6) becomes (2, -3). The zero point is
+    // returned unchanged.
+    #[must_use]
+    pub fn reduced(&self) -> Point2 {
+        let divisor = gcd(self.x.unsigned_abs(), self.y.unsigned_abs());
+        if divisor == 0 {
+            return *self;
+        }
+        let divisor = i64::from(divisor);
+        Point2::new(
+            i32::try_from(i64::from(self.x) / divisor).unwrap(),
+            i32::try_from(i64::from(self.y) / divisor).unwrap(),
+        )
+    }
+
     #[must_use]
     pub fn manhattan_distance(a: &Self, b: &Self) -> u32 {
         i32::abs_diff(a.x, b.x) + i32::abs_diff(a.y, b.y)
@@ -131,6 +156,46 @@ impl Vector2 {
     pub const fn new(x: i32, y: i32) -> Self {
         Self { x, y }
     }
+
+    // The z component of the 3D cross product. Positive if `rhs` is clockwise from `self` when y
+    // increases downwards.
+    #[must_use]
+    pub fn cross(self, rhs: Vector2) -> i64 {
+        i64::from(self.x) * i64::from(rhs.y) - i64::from(self.y) * i64::from(rhs.x)
+    }
+
+    #[must_use]
+    pub fn dot(self, rhs: Vector2) -> i64 {
+        i64::from(self.x) * i64::from(rhs.x) + i64::from(self.y) * i64::from(rhs.y)
+    }
+}
+
+// Grid directions, assuming that y increases downwards.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+pub const DIRECTION_DELTAS: [(Direction, Vector2); 4] = [
+    (Direction::Up, Direction::Up.delta()),
+    (Direction::Right, Direction::Right.delta()),
+    (Direction::Down, Direction::Down.delta()),
+    (Direction::Left, Direction::Left.delta()),
+];
+
+impl Direction {
+    #[must_use]
+    pub const fn delta(self) -> Vector2 {
+        match self {
+            Direction::Up => Vector2::new(0, -1),
+            Direction::Right => Vector2::new(1, 0),
+            Direction::Down => Vector2::new(0, 1),
+            Direction::Left => Vector2::new(-1, 0),
+        }
+    }
 }
 
 // TODO: Maybe this should be a rectangle class?
@@ -180,7 +245,7 @@ impl Bounds2 {
     pub fn outset(&self, n: i32) -> Self {
         Bounds2 {
             min: Point2::new(self.min.x - n, self.min.y - n),
-            max: Point2::new(self.max.x + n, self.max.x + n),
+            max: Point2::new(self.max.x + n, self.max.y + n),
         }
     }
 
@@ -193,6 +258,100 @@ impl Bounds2 {
     }
 }
 
+// Returns the points in row-major order, for when a deterministic ordering is needed.
+#[must_use]
+pub fn sorted_points(set: &HashSet<Point2>) -> Vec<Point2> {
+    let mut points = set.iter().copied().collect::<Vec<_>>();
+    points.sort_by_key(|p| (p.y, p.x));
+    points
+}
+
+// Maps `p` in a `width` x `height` grid to its position after rotating the grid clockwise by
+// `quarter_turns` quarter turns. With y increasing downward, each quarter turn takes (x, y) to
+// (height - 1 - y, x) and swaps the grid's width and height.
+#[must_use]
+pub fn rotate_point_in_grid(p: Point2, width: i32, height: i32, quarter_turns: u8) -> Point2 {
+    match quarter_turns % 4 {
+        0 => p,
+        1 => Point2::new(height - 1 - p.y, p.x),
+        2 => Point2::new(width - 1 - p.x, height - 1 - p.y),
+        3 => Point2::new(p.y, width - 1 - p.x),
+        _ => unreachable!(),
+    }
+}
+
+// Returns the point where the segments a1-a2 and b1-b2 (endpoints included) meet. Returns None if
+// they don't meet, or if they cross at a point with non-integer coordinates. Collinear segments
+// that overlap return the point of the overlap nearest to a1.
+#[must_use]
+pub fn segment_intersection(a1: Point2, a2: Point2, b1: Point2, b2: Point2) -> Option<Point2> {
+    let (da, db, offset) = (a2 - a1, b2 - b1, b1 - a1);
+    let mut denominator = da.cross(db);
+    if denominator == 0 {
+        if da == Vector2::new(0, 0) && db == Vector2::new(0, 0) {
+            return (a1 == b1).then_some(a1);
+        }
+        if da == Vector2::new(0, 0) {
+            return point_on_segment(a1, b1, b2).then_some(a1);
+        }
+        if offset.cross(da) != 0 {
+            // Parallel but not collinear.
+            return None;
+        }
+        // Collinear: project b's endpoints onto a, where a runs from 0 to |da|^2.
+        let (tb1, tb2) = (offset.dot(da), (b2 - a1).dot(da));
+        let (low, high) = (tb1.min(tb2), tb1.max(tb2));
+        if high < 0 || low > da.dot(da) {
+            return None;
+        }
+        return Some(if low <= 0 {
+            a1
+        } else if low == tb1 {
+            b1
+        } else {
+            b2
+        });
+    }
+
+    // a1 + t * da == b1 + u * db, where t = t_numerator / denominator and likewise for u.
+    let mut t_numerator = offset.cross(db);
+    let mut u_numerator = offset.cross(da);
+    if denominator < 0 {
+        (denominator, t_numerator, u_numerator) = (-denominator, -t_numerator, -u_numerator);
+    }
+    if !(0..=denominator).contains(&t_numerator) || !(0..=denominator).contains(&u_numerator) {
+        return None;
+    }
+    let x_offset = i64::from(da.x) * t_numerator;
+    let y_offset = i64::from(da.y) * t_numerator;
+    if x_offset % denominator != 0 || y_offset % denominator != 0 {
+        return None;
+    }
+    Some(Point2::new(
+        a1.x + i32::try_from(x_offset / denominator).unwrap(),
+        a1.y + i32::try_from(y_offset / denominator).unwrap(),
+    ))
+}
+
+// Reduces a closed path, given as consecutive points with an implicit step from the last back to
+// the first, to just the vertices where it changes direction.
+#[must_use]
+pub fn path_to_polygon(path: &[Point2]) -> Vec<Point2> {
+    let n = path.len();
+    (0..n)
+        .filter(|i| {
+            let (prev, p, next) = (path[(i + n - 1) % n], path[*i], path[(i + 1) % n]);
+            (p - prev).cross(next - p) != 0
+        })
+        .map(|i| path[i])
+        .collect()
+}
+
+fn point_on_segment(p: Point2, s1: Point2, s2: Point2) -> bool {
+    let (along, offset) = (s2 - s1, p - s1);
+    along.cross(offset) == 0 && (0..=along.dot(along)).contains(&offset.dot(along))
+}
+
 #[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub struct Point3 {
     pub x: i32,
@@ -207,7 +366,7 @@ impl Point3 {
     }
 
     #[must_use]
-    pub fn all_neighbors(&self) -> Neighbors3 {
+    pub fn all_neighbors(&self) -> Neighbors3<'_> {
         const NEIGHBOR_VECTORS: [Vector3; 14] = [
             Vector3::new(-1, 0, 0),
             Vector3::new(1, 0, 0),
@@ -232,7 +391,7 @@ impl Point3 {
     }
 
     #[must_use]
-    pub fn cardinal_neighbors(&self) -> Neighbors3 {
+    pub fn cardinal_neighbors(&self) -> Neighbors3<'_> {
         const NEIGHBOR_VECTORS: [Vector3; 6] = [
             Vector3::new(-1, 0, 0),
             Vector3::new(1, 0, 0),
@@ -249,7 +408,7 @@ impl Point3 {
     }
 
     #[must_use]
-    pub fn diagonal_neighbors(&self) -> Neighbors3 {
+    pub fn diagonal_neighbors(&self) -> Neighbors3<'_> {
         const NEIGHBOR_VECTORS: [Vector3; 8] = [
             Vector3::new(-1, -1, -1),
             Vector3::new(-1, -1, 1),
@@ -327,7 +486,7 @@ impl Bounds3 {
     pub fn outset(&self, n: i32) -> Self {
         Bounds3 {
             min: Point3::new(self.min.x - n, self.min.y - n, self.min.z - n),
-            max: Point3::new(self.max.x + n, self.max.x + n, self.max.z + n),
+            max: Point3::new(self.max.x + n, self.max.y + n, self.max.z + n),
         }
     }
 
@@ -359,3 +518,233 @@ impl Bounds3 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn direction_delta() {
+        let p = Point2::new(3, 3);
+        assert_eq!(Point2::new(3, 2), p + Direction::Up.delta());
+        assert_eq!(Point2::new(4, 3), p + Direction::Right.delta());
+        assert_eq!(Point2::new(3, 4), p + Direction::Down.delta());
+        assert_eq!(Point2::new(2, 3), p + Direction::Left.delta());
+        for (direction, delta) in DIRECTION_DELTAS {
+            assert_eq!(delta, direction.delta());
+        }
+    }
+
+    #[test]
+    fn checked_add() {
+        assert_eq!(
+            Some(Point2::new(1, -1)),
+            Point2::new(0, 0).checked_add(Vector2::new(1, -1))
+        );
+        assert_eq!(
+            None,
+            Point2::new(i32::MAX, 0).checked_add(Vector2::new(1, 0))
+        );
+        assert_eq!(
+            None,
+            Point2::new(0, i32::MIN).checked_add(Vector2::new(0, -1))
+        );
+    }
+
+    #[test]
+    fn sorted_points_row_major() {
+        let set = HashSet::from([
+            Point2::new(1, 1),
+            Point2::new(0, 1),
+            Point2::new(2, 0),
+            Point2::new(-1, 1),
+            Point2::new(0, 0),
+        ]);
+        assert_eq!(
+            vec![
+                Point2::new(0, 0),
+                Point2::new(2, 0),
+                Point2::new(-1, 1),
+                Point2::new(0, 1),
+                Point2::new(1, 1),
+            ],
+            sorted_points(&set)
+        );
+    }
+
+    #[test]
+    fn rotate_corner() {
+        // The top-right corner of a grid 3 wide and 5 tall.
+        let p = Point2::new(2, 0);
+        assert_eq!(p, rotate_point_in_grid(p, 3, 5, 0));
+        // Now 5 wide and 3 tall: bottom-right.
+        assert_eq!(Point2::new(4, 2), rotate_point_in_grid(p, 3, 5, 1));
+        // 3 wide and 5 tall again: bottom-left.
+        assert_eq!(Point2::new(0, 4), rotate_point_in_grid(p, 3, 5, 2));
+        // 5 wide and 3 tall: top-left.
+        assert_eq!(Point2::new(0, 0), rotate_point_in_grid(p, 3, 5, 3));
+        assert_eq!(p, rotate_point_in_grid(p, 3, 5, 4));
+    }
+
+    #[test]
+    fn rotate_composes() {
+        let (width, height) = (3, 5);
+        for y in 0..height {
+            for x in 0..width {
+                let p = Point2::new(x, y);
+                let mut rotated = p;
+                let (mut w, mut h) = (width, height);
+                for turns in 1..=4 {
+                    rotated = rotate_point_in_grid(rotated, w, h, 1);
+                    (w, h) = (h, w);
+                    assert_eq!(rotated, rotate_point_in_grid(p, width, height, turns));
+                    assert!(rotated.x >= 0 && rotated.x < w && rotated.y >= 0 && rotated.y < h);
+                }
+                assert_eq!(p, rotated);
+            }
+        }
+    }
+
+    #[test]
+    fn reduced() {
+        assert_eq!(Point2::new(2, 3), Point2::new(4, 6).reduced());
+        assert_eq!(Point2::new(0, 1), Point2::new(0, 5).reduced());
+        assert_eq!(Point2::new(-1, 0), Point2::new(-7, 0).reduced());
+        assert_eq!(Point2::new(2, -3), Point2::new(4, -6).reduced());
+        assert_eq!(Point2::new(0, 0), Point2::new(0, 0).reduced());
+        assert_eq!(Point2::new(-1, 0), Point2::new(i32::MIN, 0).reduced());
+    }
+
+    #[test]
+    fn segments_crossing() {
+        let p = Point2::new;
+        assert_eq!(
+            Some(p(2, 1)),
+            segment_intersection(p(0, 1), p(5, 1), p(2, -3), p(2, 4))
+        );
+        assert_eq!(
+            Some(p(2, 2)),
+            segment_intersection(p(0, 0), p(4, 4), p(0, 4), p(4, 0))
+        );
+        // Touching at an endpoint.
+        assert_eq!(
+            Some(p(5, 1)),
+            segment_intersection(p(0, 1), p(5, 1), p(5, 1), p(5, 9))
+        );
+        // The lines cross at (0.5, 0.5), which isn't an integer point.
+        assert_eq!(
+            None,
+            segment_intersection(p(0, 0), p(1, 1), p(0, 1), p(1, 0))
+        );
+        // The lines cross, but beyond the end of the second segment.
+        assert_eq!(
+            None,
+            segment_intersection(p(0, 1), p(5, 1), p(2, 2), p(2, 4))
+        );
+    }
+
+    #[test]
+    fn segments_parallel() {
+        let p = Point2::new;
+        assert_eq!(
+            None,
+            segment_intersection(p(0, 0), p(5, 0), p(0, 1), p(5, 1))
+        );
+        assert_eq!(
+            None,
+            segment_intersection(p(0, 0), p(2, 2), p(1, 0), p(3, 2))
+        );
+    }
+
+    #[test]
+    fn segments_collinear() {
+        let p = Point2::new;
+        assert_eq!(
+            Some(p(3, 0)),
+            segment_intersection(p(0, 0), p(5, 0), p(7, 0), p(3, 0))
+        );
+        assert_eq!(
+            Some(p(0, 0)),
+            segment_intersection(p(0, 0), p(5, 0), p(-2, 0), p(1, 0))
+        );
+        assert_eq!(
+            Some(p(5, 0)),
+            segment_intersection(p(5, 0), p(0, 0), p(-2, 0), p(9, 0))
+        );
+        assert_eq!(
+            None,
+            segment_intersection(p(0, 0), p(5, 0), p(6, 0), p(9, 0))
+        );
+        assert_eq!(
+            Some(p(2, 2)),
+            segment_intersection(p(2, 2), p(2, 2), p(0, 0), p(4, 4))
+        );
+        assert_eq!(
+            None,
+            segment_intersection(p(2, 3), p(2, 3), p(0, 0), p(4, 4))
+        );
+        // Both segments are single points.
+        assert_eq!(
+            None,
+            segment_intersection(p(0, 0), p(0, 0), p(5, 5), p(5, 5))
+        );
+        assert_eq!(
+            Some(p(5, 5)),
+            segment_intersection(p(5, 5), p(5, 5), p(5, 5), p(5, 5))
+        );
+        // Only b is a single point.
+        assert_eq!(
+            Some(p(3, 0)),
+            segment_intersection(p(0, 0), p(5, 0), p(3, 0), p(3, 0))
+        );
+        assert_eq!(
+            None,
+            segment_intersection(p(0, 0), p(5, 0), p(3, 1), p(3, 1))
+        );
+    }
+
+    #[test]
+    fn outset() {
+        let bounds = Bounds2 {
+            min: Point2::new(0, 10),
+            max: Point2::new(3, 20),
+        }
+        .outset(2);
+        assert_eq!(Point2::new(-2, 8), bounds.min);
+        assert_eq!(Point2::new(5, 22), bounds.max);
+        let bounds = Bounds3 {
+            min: Point3::new(0, 10, 100),
+            max: Point3::new(3, 20, 300),
+        }
+        .outset(1);
+        assert!(Point3::new(-1, 9, 99) == bounds.min);
+        assert!(Point3::new(4, 21, 301) == bounds.max);
+    }
+
+    #[test]
+    fn rectangle_path_to_polygon() {
+        // Clockwise around the border of a 4x3 rectangle, starting mid-edge.
+        let path = [
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 1),
+            (0, 0),
+        ]
+        .map(|(x, y)| Point2::new(x, y));
+        assert_eq!(
+            vec![
+                Point2::new(3, 0),
+                Point2::new(3, 2),
+                Point2::new(0, 2),
+                Point2::new(0, 0),
+            ],
+            path_to_polygon(&path)
+        );
+    }
+}
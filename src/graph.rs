@@ -0,0 +1,88 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// A directed graph with weighted edges.
+#[derive(Clone, Debug)]
+pub struct Graph<N> {
+    edges: HashMap<N, Vec<(N, u64)>>,
+}
+
+impl<N> Default for Graph<N> {
+    fn default() -> Self {
+        Graph {
+            edges: HashMap::new(),
+        }
+    }
+}
+
+impl<N: Copy + Eq + Hash> Graph<N> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, node: N) {
+        self.edges.entry(node).or_default();
+    }
+
+    pub fn add_edge(&mut self, from: N, to: N, weight: u64) {
+        self.add_node(to);
+        self.edges.entry(from).or_default().push((to, weight));
+    }
+
+    #[must_use]
+    pub fn contains(&self, node: &N) -> bool {
+        self.edges.contains_key(node)
+    }
+
+    #[must_use]
+    pub fn neighbors(&self, node: &N) -> &[(N, u64)] {
+        self.edges.get(node).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &N> {
+        self.edges.keys()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edges() {
+        let mut graph = Graph::new();
+        graph.add_edge('a', 'b', 3);
+        graph.add_edge('a', 'c', 1);
+        graph.add_node('d');
+        assert_eq!(4, graph.len());
+        assert_eq!(&[('b', 3), ('c', 1)], graph.neighbors(&'a'));
+        assert!(graph.neighbors(&'b').is_empty());
+        assert!(graph.contains(&'d'));
+        assert!(!graph.contains(&'e'));
+    }
+}
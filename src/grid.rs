@@ -0,0 +1,391 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::geometry::{Bounds2, Point2};
+use crate::graph::Graph;
+use crate::matrix::Matrix;
+use crate::{oops, oops::Oops};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+// A rectangular character grid addressed by `Point2`, with (0, 0) at the top left and y increasing
+// downwards.
+#[derive(Debug)]
+pub struct Grid {
+    cells: Matrix<char>,
+    bounds: Bounds2,
+}
+
+impl FromStr for Grid {
+    type Err = Oops;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cells = parse_matrix(s)?;
+        let bounds = Bounds2 {
+            min: Point2::new(0, 0),
+            max: Point2::new(
+                i32::try_from(cells.width())? - 1,
+                i32::try_from(cells.height())? - 1,
+            ),
+        };
+        Ok(Grid { cells, bounds })
+    }
+}
+
+impl Grid {
+    #[must_use]
+    pub fn cells(&self) -> &Matrix<char> {
+        &self.cells
+    }
+
+    #[must_use]
+    pub fn bounds(&self) -> &Bounds2 {
+        &self.bounds
+    }
+
+    // Returns None for points outside the grid.
+    #[must_use]
+    pub fn get(&self, p: Point2) -> Option<char> {
+        self.cells.get_point(p)
+    }
+
+    // Every point in the grid, in reading order.
+    pub fn points(&self) -> impl Iterator<Item = Point2> + '_ {
+        (self.bounds.min.y..=self.bounds.max.y)
+            .flat_map(|y| (self.bounds.min.x..=self.bounds.max.x).map(move |x| Point2::new(x, y)))
+    }
+}
+
+// Builds a graph where every passable cell is connected to its passable cardinal neighbors. The
+// weight of an edge is the weight of the cell being entered.
+pub fn to_graph<T, P, W>(grid: &Matrix<T>, passable: P, weight: W) -> Graph<Point2>
+where
+    T: Copy,
+    P: Fn(&T) -> bool,
+    W: Fn(&T) -> u64,
+{
+    let mut graph = Graph::new();
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            let p = Point2::new(x.try_into().unwrap(), y.try_into().unwrap());
+            if !passable(&grid.get(x, y)) {
+                continue;
+            }
+            graph.add_node(p);
+            for neighbor in p.cardinal_neighbors() {
+                if let Some(cell) = grid.get_point(neighbor).filter(|cell| passable(cell)) {
+                    graph.add_edge(p, neighbor, weight(&cell));
+                }
+            }
+        }
+    }
+    graph
+}
+
+// Maps each character's (x, y) position to the character, with y increasing downwards. Characters
+// equal to `empty` are left out.
+#[must_use]
+pub fn parse_points(s: &str, empty: Option<char>) -> HashMap<Point2, char> {
+    (0i32..)
+        .zip(s.lines())
+        .flat_map(|(y, line)| {
+            (0i32..)
+                .zip(line.chars())
+                .map(move |(x, c)| (Point2::new(x, y), c))
+        })
+        .filter(|(_, c)| Some(*c) != empty)
+        .collect()
+}
+
+// Parses a rectangular grid of characters, one row per line.
+pub fn parse_matrix(s: &str) -> Result<Matrix<char>, Oops> {
+    let mut width = None;
+    let mut height = 0;
+    let mut data = vec![];
+    for line in s.lines() {
+        let len = line.chars().count();
+        match width {
+            None => width = Some(len),
+            Some(width) if width != len => {
+                return Err(oops!("row {height} has {len} columns, expected {width}"))
+            }
+            _ => (),
+        }
+        data.extend(line.chars());
+        height += 1;
+    }
+    Ok(Matrix::from_vec(width.unwrap_or(0), height, data))
+}
+
+// Parses a rectangular grid of `sep`-delimited values. A whitespace separator matches any run of
+// whitespace.
+pub fn parse_number_grid<T>(s: &str, sep: char) -> Result<Matrix<T>, Oops>
+where
+    T: Copy + FromStr,
+    T::Err: std::error::Error + 'static,
+{
+    let mut width = None;
+    let mut height = 0;
+    let mut data = vec![];
+    for line in s.lines() {
+        let row = if sep.is_whitespace() {
+            line.split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<T>, _>>()?
+        } else {
+            line.split(sep)
+                .map(|value| value.trim().parse())
+                .collect::<Result<Vec<T>, _>>()?
+        };
+        match width {
+            None => width = Some(row.len()),
+            Some(width) if width != row.len() => {
+                return Err(oops!(
+                    "row {height} has {} values, expected {width}",
+                    row.len()
+                ))
+            }
+            _ => (),
+        }
+        data.extend(row);
+        height += 1;
+    }
+    Ok(Matrix::from_vec(width.unwrap_or(0), height, data))
+}
+
+// Shifts every point so the minimum x and y coordinates are both 0. Returns the shifted cells and
+// the offset that was subtracted from each point, i.e. the original position of the new origin.
+// Fails if the points span more than i32::MAX in either direction.
+pub fn normalize_sparse<T>(
+    cells: HashMap<Point2, T>,
+) -> Result<(HashMap<Point2, T>, Point2), Oops> {
+    if cells.is_empty() {
+        return Ok((cells, Point2::new(0, 0)));
+    }
+    let offset = Bounds2::from_points(cells.keys()).min;
+    let cells = cells
+        .into_iter()
+        .map(
+            |(p, v)| match (p.x.checked_sub(offset.x), p.y.checked_sub(offset.y)) {
+                (Some(x), Some(y)) => Ok((Point2::new(x, y), v)),
+                _ => Err(oops!("{p:?} is too far from {offset:?} to normalize")),
+            },
+        )
+        .collect::<Result<_, _>>()?;
+    Ok((cells, offset))
+}
+
+// Adds a one-cell border of `fill` around the bounding box of `map`, so every original cell's
+// neighbors are present. Gaps inside the bounding box are left alone.
+#[must_use]
+pub fn with_border<T: Clone>(mut map: HashMap<Point2, T>, fill: T) -> HashMap<Point2, T> {
+    if map.is_empty() {
+        return map;
+    }
+    let bounds = Bounds2::from_points(map.keys()).outset(1);
+    for x in bounds.min.x..=bounds.max.x {
+        map.insert(Point2::new(x, bounds.min.y), fill.clone());
+        map.insert(Point2::new(x, bounds.max.y), fill.clone());
+    }
+    for y in bounds.min.y + 1..bounds.max.y {
+        map.insert(Point2::new(bounds.min.x, y), fill.clone());
+        map.insert(Point2::new(bounds.max.x, y), fill.clone());
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiny_maze() {
+        // 1#3
+        // 245
+        let mut grid = Matrix::new(3, 2, '#');
+        grid.set(0, 0, '1');
+        grid.set(2, 0, '3');
+        grid.set(0, 1, '2');
+        grid.set(1, 1, '4');
+        grid.set(2, 1, '5');
+
+        let graph = to_graph(&grid, |c| *c != '#', |c| c.to_digit(10).unwrap().into());
+        assert_eq!(5, graph.len());
+        assert!(!graph.contains(&Point2::new(1, 0)));
+        assert_eq!(
+            &[(Point2::new(0, 1), 2)],
+            graph.neighbors(&Point2::new(0, 0))
+        );
+        assert_eq!(
+            &[(Point2::new(0, 1), 2), (Point2::new(2, 1), 5)],
+            graph.neighbors(&Point2::new(1, 1))
+        );
+        assert_eq!(
+            &[(Point2::new(2, 1), 5)],
+            graph.neighbors(&Point2::new(2, 0))
+        );
+    }
+
+    #[test]
+    fn grid() {
+        let grid = "#.O\n..#\n".parse::<Grid>().unwrap();
+        assert_eq!(Point2::new(0, 0), grid.bounds().min);
+        assert_eq!(Point2::new(2, 1), grid.bounds().max);
+        assert_eq!(3, grid.cells().width());
+        assert_eq!(Some('O'), grid.get(Point2::new(2, 0)));
+        assert_eq!(Some('#'), grid.get(Point2::new(2, 1)));
+        assert_eq!(None, grid.get(Point2::new(3, 0)));
+        assert_eq!(None, grid.get(Point2::new(0, 2)));
+        assert_eq!(None, grid.get(Point2::new(-1, 0)));
+        assert_eq!(
+            "#.O..#",
+            grid.points()
+                .map(|p| grid.get(p).unwrap())
+                .collect::<String>()
+        );
+        assert!("#.\n.\n".parse::<Grid>().is_err());
+    }
+
+    #[test]
+    fn empty_grid() {
+        let grid = "".parse::<Grid>().unwrap();
+        assert_eq!(0, grid.points().count());
+        assert_eq!(None, grid.get(Point2::new(0, 0)));
+    }
+
+    #[test]
+    fn char_matrix() {
+        let grid = parse_matrix("#.O\n..#\n").unwrap();
+        assert_eq!(3, grid.width());
+        assert_eq!(2, grid.height());
+        assert_eq!(
+            vec!['#', '.', 'O'],
+            grid.row(0).copied().collect::<Vec<_>>()
+        );
+        assert_eq!(vec!['O', '#'], grid.col(2).copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn jagged_char_matrix() {
+        assert!(parse_matrix("#.O\n.#\n").is_err());
+    }
+
+    #[test]
+    fn number_grid() {
+        let grid = parse_number_grid::<i64>("1,-2,3\n4, 5 ,6\n", ',').unwrap();
+        assert_eq!(3, grid.width());
+        assert_eq!(2, grid.height());
+        assert_eq!(vec![1, -2, 3], grid.row(0).copied().collect::<Vec<_>>());
+        assert_eq!(vec![4, 5, 6], grid.row(1).copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn number_grid_whitespace() {
+        let grid = parse_number_grid::<u32>("1  2\t3\n4 5 6\n", ' ').unwrap();
+        assert_eq!(vec![3, 6], grid.col(2).copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn number_grid_errors() {
+        assert!(parse_number_grid::<i64>("1,2\n3\n", ',').is_err());
+        assert!(parse_number_grid::<i64>("1,x\n", ',').is_err());
+    }
+
+    #[test]
+    fn normalize_negative_coordinates() {
+        let cells = HashMap::from([
+            (Point2::new(-3, 2), 'a'),
+            (Point2::new(1, -4), 'b'),
+            (Point2::new(0, 0), 'c'),
+        ]);
+        let (cells, offset) = normalize_sparse(cells).unwrap();
+        assert_eq!(Point2::new(-3, -4), offset);
+        assert_eq!(
+            HashMap::from([
+                (Point2::new(0, 6), 'a'),
+                (Point2::new(4, 0), 'b'),
+                (Point2::new(3, 4), 'c'),
+            ]),
+            cells
+        );
+    }
+
+    #[test]
+    fn normalize_empty() {
+        let (cells, offset) = normalize_sparse(HashMap::<Point2, ()>::new()).unwrap();
+        assert!(cells.is_empty());
+        assert_eq!(Point2::new(0, 0), offset);
+    }
+
+    #[test]
+    fn normalize_too_wide() {
+        let cells = HashMap::from([
+            (Point2::new(i32::MIN, 0), ()),
+            (Point2::new(i32::MAX, 0), ()),
+        ]);
+        assert!(normalize_sparse(cells).is_err());
+        let cells = HashMap::from([(Point2::new(0, i32::MIN), ()), (Point2::new(0, -1), ())]);
+        assert_eq!(
+            Point2::new(0, i32::MAX),
+            normalize_sparse(cells)
+                .unwrap()
+                .0
+                .into_keys()
+                .max_by_key(|p| p.y)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn border() {
+        let map = HashMap::from([(Point2::new(0, 0), 'F'), (Point2::new(1, -1), '7')]);
+        let bordered = with_border(map, '.');
+        // The 2x2 bounding box grows to 4x4; (0, -1) and (1, 0) were never present.
+        assert_eq!(2 + 12, bordered.len());
+        assert_eq!(Some(&'F'), bordered.get(&Point2::new(0, 0)));
+        assert_eq!(Some(&'7'), bordered.get(&Point2::new(1, -1)));
+        assert_eq!(None, bordered.get(&Point2::new(0, -1)));
+        for p in [
+            Point2::new(-1, -2),
+            Point2::new(2, -2),
+            Point2::new(-1, 1),
+            Point2::new(2, 1),
+            Point2::new(0, -2),
+            Point2::new(-1, 0),
+            Point2::new(2, -1),
+            Point2::new(1, 1),
+        ] {
+            assert_eq!(Some(&'.'), bordered.get(&p), "{p:?}");
+        }
+        assert!(with_border(HashMap::new(), '.').is_empty());
+    }
+
+    #[test]
+    fn points() {
+        let grid = ".#\nS.\n";
+        assert_eq!(
+            HashMap::from([
+                (Point2::new(0, 0), '.'),
+                (Point2::new(1, 0), '#'),
+                (Point2::new(0, 1), 'S'),
+                (Point2::new(1, 1), '.'),
+            ]),
+            parse_points(grid, None)
+        );
+        assert_eq!(
+            HashMap::from([(Point2::new(1, 0), '#'), (Point2::new(0, 1), 'S')]),
+            parse_points(grid, Some('.'))
+        );
+    }
+}
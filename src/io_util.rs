@@ -0,0 +1,159 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{oops, oops::Oops};
+use std::io::{self, Read};
+use std::path::Path;
+
+// Reads the puzzle input from the file named by the first positional command-line argument,
+// falling back to stdin if there is none.
+pub fn read_input() -> Result<String, Oops> {
+    read_input_from(input_path(std::env::args().skip(1)))
+}
+
+pub fn read_input_from<P: AsRef<Path>>(path: Option<P>) -> Result<String, Oops> {
+    match path {
+        Some(path) => Ok(std::fs::read_to_string(path)?),
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+    }
+}
+
+// Returns which of (part1, part2) to run, based on an optional `--part 1|2` argument. With no
+// argument, both parts are run.
+pub fn selected_parts() -> Result<(bool, bool), Oops> {
+    parse_selected_parts(std::env::args().skip(1))
+}
+
+// Whether `--verbose` was passed, for days that can print intermediate state.
+#[must_use]
+pub fn verbose() -> bool {
+    flag("--verbose")
+}
+
+// Whether a day-specific switch such as `--reverse` was passed.
+#[must_use]
+pub fn flag(name: &str) -> bool {
+    has_flag(std::env::args().skip(1), name)
+}
+
+fn has_flag<I: IntoIterator<Item = String>>(args: I, name: &str) -> bool {
+    args.into_iter().any(|arg| arg == name)
+}
+
+fn input_path<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--part" {
+            args.next();
+        } else if !arg.starts_with("--") {
+            return Some(arg);
+        }
+    }
+    None
+}
+
+fn parse_selected_parts<I: IntoIterator<Item = String>>(args: I) -> Result<(bool, bool), Oops> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--part" {
+            return match args.next().as_deref() {
+                Some("1") => Ok((true, false)),
+                Some("2") => Ok((false, true)),
+                Some(part) => Err(oops!("unknown part {part}")),
+                None => Err(oops!("--part requires a value")),
+            };
+        }
+    }
+    Ok((true, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_from_path() {
+        let path =
+            std::env::temp_dir().join(format!("aoc_2023_io_util_{}.txt", std::process::id()));
+        std::fs::write(&path, "1abc2\npqr3stu8vwx\n").unwrap();
+        let input = read_input_from(Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("1abc2\npqr3stu8vwx\n", input.unwrap());
+    }
+
+    #[test]
+    fn read_from_missing_path() {
+        assert!(read_input_from(Some("/nonexistent/aoc_2023/input.txt")).is_err());
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn selected_parts() {
+        assert_eq!((true, true), parse_selected_parts(args(&[])).unwrap());
+        assert_eq!(
+            (true, true),
+            parse_selected_parts(args(&["input.txt"])).unwrap()
+        );
+        assert_eq!(
+            (true, false),
+            parse_selected_parts(args(&["--part", "1"])).unwrap()
+        );
+        assert_eq!(
+            (false, true),
+            parse_selected_parts(args(&["input.txt", "--part", "2"])).unwrap()
+        );
+        assert!(parse_selected_parts(args(&["--part", "3"])).is_err());
+        assert!(parse_selected_parts(args(&["--part"])).is_err());
+    }
+
+    #[test]
+    fn input_path_skips_flags() {
+        assert_eq!(None, input_path(args(&[])));
+        assert_eq!(None, input_path(args(&["--part", "1"])));
+        assert_eq!(
+            Some("input.txt".to_string()),
+            input_path(args(&["--part", "1", "input.txt"]))
+        );
+        assert_eq!(
+            Some("input.txt".to_string()),
+            input_path(args(&["input.txt", "--part", "2"]))
+        );
+    }
+
+    #[test]
+    fn named_flags() {
+        assert!(has_flag(args(&["--reverse", "input.txt"]), "--reverse"));
+        assert!(!has_flag(args(&["--reverse", "input.txt"]), "--columns"));
+        // Only exact matches count; positional arguments aren't flags.
+        assert!(!has_flag(args(&["--reversed"]), "--reverse"));
+        assert!(!has_flag(args(&["reverse"]), "--reverse"));
+    }
+
+    #[test]
+    fn verbose_flag() {
+        assert!(!has_flag(args(&["input.txt"]), "--verbose"));
+        assert!(has_flag(args(&["input.txt", "--verbose"]), "--verbose"));
+        assert_eq!(
+            Some("input.txt".to_string()),
+            input_path(args(&["--verbose", "input.txt"]))
+        );
+    }
+}
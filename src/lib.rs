@@ -12,9 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod bits;
+pub mod calibration;
+pub mod collections;
 pub mod geometry;
+pub mod graph;
+pub mod grid;
+pub mod io_util;
 pub mod itertools;
 pub mod math;
 pub mod matrix;
 pub mod oops;
+pub mod parallel;
+pub mod search;
 pub mod timing;
+pub mod union_find;
@@ -12,6 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::geometry::Point2;
+use std::collections::HashMap;
+
+// Intended for non-negative values: with negative inputs, the comparison and remainder steps don't
+// converge on the right answer. Use `gcd_signed()` for signed inputs.
 pub fn gcd<T>(mut a: T, mut b: T) -> T
 where
     T: Copy + Default + Ord + std::ops::Rem<Output = T>,
@@ -31,6 +36,13 @@ where
     }
 }
 
+// Always non-negative. Panics if the result is 2^63, which only happens when both inputs are
+// i64::MIN or 0.
+#[must_use]
+pub fn gcd_signed(a: i64, b: i64) -> i64 {
+    i64::try_from(gcd(a.unsigned_abs(), b.unsigned_abs())).unwrap()
+}
+
 pub fn lcm<T>(a: T, b: T) -> T
 where
     T: Copy
@@ -43,6 +55,207 @@ where
     a * (b / gcd(a, b))
 }
 
+// Returns 0 for an empty iterator, since gcd(0, n) == n.
+pub fn gcd_all<T, I>(iter: I) -> T
+where
+    T: Copy + Default + Ord + std::ops::Rem<Output = T>,
+    I: IntoIterator<Item = T>,
+{
+    iter.into_iter().fold(T::default(), gcd)
+}
+
+// Returns 1 for an empty iterator, since lcm(1, n) == n.
+pub fn lcm_all<T, I>(iter: I) -> T
+where
+    T: Copy
+        + Default
+        + From<u8>
+        + Ord
+        + std::ops::Mul<Output = T>
+        + std::ops::Div<Output = T>
+        + std::ops::Rem<Output = T>,
+    I: IntoIterator<Item = T>,
+{
+    iter.into_iter().fold(T::from(1), lcm)
+}
+
+#[must_use]
+pub fn checked_lcm(a: u64, b: u64) -> Option<u64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    a.checked_mul(b / gcd(a, b))
+}
+
+// Returns None if any intermediate result overflows.
+pub fn checked_lcm_all<I: IntoIterator<Item = u64>>(iter: I) -> Option<u64> {
+    iter.into_iter().try_fold(1, checked_lcm)
+}
+
+// Returns a map of prime factor to exponent. 1 has no prime factors.
+#[must_use]
+pub fn prime_factors(mut n: u64) -> HashMap<u64, u32> {
+    let mut factors = HashMap::new();
+    let mut divisor = 2;
+    // Dividing rather than squaring keeps the bound from overflowing near u64::MAX.
+    while divisor <= n / divisor {
+        while n.is_multiple_of(divisor) {
+            *factors.entry(divisor).or_insert(0) += 1;
+            n /= divisor;
+        }
+        // After 2, only odd divisors can be prime.
+        divisor += if divisor == 2 { 1 } else { 2 };
+    }
+    if n > 1 {
+        *factors.entry(n).or_insert(0) += 1;
+    }
+    factors
+}
+
+// Returns (g, x, y) such that a * x + b * y == g, where g is the gcd of a and b.
+#[must_use]
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+// Returns x in 0..m such that a * x is 1 mod m, or None if there is no such x (including when m
+// isn't positive).
+#[must_use]
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    if m <= 0 {
+        return None;
+    }
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    if g == 1 {
+        Some(x.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+// Returns base^exp mod modulus. Panics if modulus is 0.
+#[must_use]
+pub fn mod_pow(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    assert!(modulus > 0, "modulus must be positive");
+    if modulus == 1 {
+        return 0;
+    }
+    // Intermediate products are done in u128 so they can't overflow.
+    let modulus = u128::from(modulus);
+    let mut base = u128::from(base) % modulus;
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+    u64::try_from(result).unwrap()
+}
+
+// Returns floor(sqrt(n)), computed exactly with Newton's method.
+#[must_use]
+pub fn integer_sqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    // Starting at or above the root, the iterates decrease monotonically until they reach it.
+    let mut x = n;
+    let mut y = n / 2 + n % 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+// Fits the lowest-degree polynomial through (i, values[i]) for every index i, then evaluates it
+// `steps` past the last index, i.e. at x = n - 1 + steps, using Lagrange interpolation. Each
+// basis polynomial evaluates to an integer at integer x, so the arithmetic is exact.
+#[must_use]
+pub fn extrapolate(values: &[i64], steps: i64) -> i64 {
+    let n = i128::try_from(values.len()).unwrap();
+    let x = n - 1 + i128::from(steps);
+    let total = (0i128..)
+        .zip(values)
+        .map(|(i, y)| {
+            let (numerator, denominator) = (0..n)
+                .filter(|j| *j != i)
+                .fold((1i128, 1i128), |(num, den), j| {
+                    (num * (x - j), den * (i - j))
+                });
+            i128::from(*y) * (numerator / denominator)
+        })
+        .sum::<i128>();
+    i64::try_from(total).unwrap()
+}
+
+// Returns the real roots of ax^2 + bx + c = 0 in ascending order, or None if there are none. A
+// repeated root is returned twice.
+#[must_use]
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        return None;
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let sqrt = discriminant.sqrt();
+    let (r1, r2) = ((-b - sqrt) / (2.0 * a), (-b + sqrt) / (2.0 * a));
+    Some((r1.min(r2), r1.max(r2)))
+}
+
+// Returns the area enclosed by a simple polygon whose vertices are given in order, with an implicit
+// edge from the last point back to the first. Twice the area is always an integer; if the area
+// itself isn't, it's rounded down.
+#[must_use]
+pub fn shoelace_area(points: &[Point2]) -> i64 {
+    let twice_area = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| i64::from(a.x) * i64::from(b.y) - i64::from(b.x) * i64::from(a.y))
+        .sum::<i64>();
+    twice_area.abs() / 2
+}
+
+// Pick's theorem: for a polygon with lattice point vertices, A = I + B/2 - 1, where `boundary` is
+// the number of lattice points on the boundary. Returns the number of lattice points in the
+// interior.
+#[must_use]
+pub fn interior_points(area: i64, boundary: i64) -> i64 {
+    (2 * area - boundary + 2) / 2
+}
+
+// Sums |a - b| over every pair of values in O(n log n). After sorting, the i-th value is at least
+// as large as the i before it, so its contribution is i * value minus the sum of the preceding
+// values.
+#[must_use]
+pub fn pairwise_distance_sum(mut values: Vec<i64>) -> u64 {
+    values.sort_unstable();
+    let mut prefix_sum = 0;
+    let mut total = 0;
+    for (i, value) in (0i64..).zip(values) {
+        total += value * i - prefix_sum;
+        prefix_sum += value;
+    }
+    u64::try_from(total).unwrap()
+}
+
+// Sums the Manhattan distance between every pair of points in O(n log n), handling each axis
+// independently.
+#[must_use]
+pub fn pairwise_manhattan_sum(points: &[Point2]) -> u64 {
+    pairwise_distance_sum(points.iter().map(|p| i64::from(p.x)).collect())
+        + pairwise_distance_sum(points.iter().map(|p| i64::from(p.y)).collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,4 +271,207 @@ mod tests {
         assert_eq!(6, lcm(2, 3));
         assert_eq!(12, lcm(4, 6));
     }
+
+    #[test]
+    fn test_gcd_all() {
+        assert_eq!(2, gcd_all([4, 6, 8]));
+        assert_eq!(7, gcd_all([7]));
+        assert_eq!(0, gcd_all(Vec::<u64>::new()));
+    }
+
+    #[test]
+    fn test_lcm_all() {
+        assert_eq!(24, lcm_all([4, 6, 8]));
+        assert_eq!(7, lcm_all([7]));
+        assert_eq!(1, lcm_all(Vec::<u64>::new()));
+    }
+
+    #[test]
+    fn test_checked_lcm() {
+        assert_eq!(Some(12), checked_lcm(4, 6));
+        assert_eq!(Some(0), checked_lcm(0, 0));
+        assert_eq!(None, checked_lcm(u64::MAX, u64::MAX - 1));
+    }
+
+    #[test]
+    fn test_checked_lcm_all() {
+        assert_eq!(Some(24), checked_lcm_all([4, 6, 8]));
+        assert_eq!(Some(1), checked_lcm_all([]));
+        // The product of the first 16 primes is larger than u64::MAX.
+        assert_eq!(
+            None,
+            checked_lcm_all([2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53])
+        );
+    }
+
+    #[test]
+    fn test_prime_factors() {
+        assert_eq!(HashMap::from([(2, 2), (3, 1)]), prime_factors(12));
+        assert_eq!(HashMap::from([(97, 1)]), prime_factors(97));
+        assert_eq!(HashMap::from([(2, 10)]), prime_factors(1024));
+        assert_eq!(HashMap::new(), prime_factors(1));
+    }
+
+    #[test]
+    fn test_prime_factors_large() {
+        // The smallest prime above 2^40, so trial division runs all the way to its square root.
+        assert_eq!(
+            HashMap::from([(1_099_511_627_791, 1)]),
+            prime_factors(1_099_511_627_791)
+        );
+        assert_eq!(
+            HashMap::from([(1_048_573, 1), (1_048_583, 1)]),
+            prime_factors(1_048_573 * 1_048_583)
+        );
+        assert_eq!(
+            HashMap::from([
+                (3, 1),
+                (5, 1),
+                (17, 1),
+                (257, 1),
+                (641, 1),
+                (65537, 1),
+                (6_700_417, 1)
+            ]),
+            prime_factors(u64::MAX)
+        );
+    }
+
+    #[test]
+    #[ignore = "trial division up to 2^32 takes several seconds"]
+    fn test_prime_factors_near_max() {
+        // The largest prime below 2^64: without an overflow-safe bound, squaring the divisor
+        // overflows before the loop ends.
+        assert_eq!(
+            HashMap::from([(18_446_744_073_709_551_557, 1)]),
+            prime_factors(18_446_744_073_709_551_557)
+        );
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let (g, x, y) = extended_gcd(240, 46);
+        assert_eq!(2, g);
+        assert_eq!(2, 240 * x + 46 * y);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(Some(4), mod_inverse(3, 11));
+        assert_eq!(Some(7), mod_inverse(-3, 11));
+        assert_eq!(None, mod_inverse(4, 8));
+        assert_eq!(None, mod_inverse(3, 0));
+        assert_eq!(None, mod_inverse(3, -11));
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(24, mod_pow(2, 10, 1000));
+        assert_eq!(1, mod_pow(7, 0, 13));
+        assert_eq!(0, mod_pow(7, 5, 1));
+        assert_eq!(1, mod_pow(u64::MAX - 1, u64::MAX - 1, u64::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "modulus must be positive")]
+    fn test_mod_pow_zero_modulus() {
+        let _ = mod_pow(2, 10, 0);
+    }
+
+    #[test]
+    fn test_pairwise_manhattan_sum() {
+        assert_eq!(0, pairwise_manhattan_sum(&[]));
+        assert_eq!(0, pairwise_manhattan_sum(&[Point2::new(3, 4)]));
+
+        // Deterministic pseudo-random points from a simple LCG.
+        let mut state = 12345u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            i32::try_from(state >> 33).unwrap() % 2000 - 1000
+        };
+        let points = (0..200)
+            .map(|_| Point2::new(next(), next()))
+            .collect::<Vec<_>>();
+        let brute_force = (0..points.len())
+            .flat_map(|i| {
+                let points = &points;
+                (i + 1..points.len())
+                    .map(move |j| u64::from(Point2::manhattan_distance(&points[i], &points[j])))
+            })
+            .sum::<u64>();
+        assert_eq!(brute_force, pairwise_manhattan_sum(&points));
+    }
+
+    #[test]
+    fn test_solve_quadratic() {
+        assert_eq!(Some((2.0, 3.0)), solve_quadratic(1.0, -5.0, 6.0));
+        assert_eq!(Some((2.0, 3.0)), solve_quadratic(-1.0, 5.0, -6.0));
+        assert_eq!(Some((1.0, 1.0)), solve_quadratic(1.0, -2.0, 1.0));
+        assert_eq!(None, solve_quadratic(1.0, 0.0, 1.0));
+        assert_eq!(None, solve_quadratic(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_shoelace_area() {
+        let square = [
+            Point2::new(0, 0),
+            Point2::new(4, 0),
+            Point2::new(4, 3),
+            Point2::new(0, 3),
+        ];
+        assert_eq!(12, shoelace_area(&square));
+        let mut reversed = square;
+        reversed.reverse();
+        assert_eq!(12, shoelace_area(&reversed));
+        assert_eq!(0, shoelace_area(&[]));
+        assert_eq!(
+            6,
+            shoelace_area(&[Point2::new(0, 0), Point2::new(4, 0), Point2::new(0, 3)])
+        );
+    }
+
+    #[test]
+    fn test_interior_points() {
+        // A 4x3 rectangle has 14 boundary points and 3 * 2 interior points.
+        assert_eq!(6, interior_points(12, 14));
+        // A unit square has no interior points.
+        assert_eq!(0, interior_points(1, 4));
+    }
+
+    #[test]
+    fn test_integer_sqrt() {
+        assert_eq!(0, integer_sqrt(0));
+        assert_eq!(1, integer_sqrt(1));
+        for root in [2u64, 3, 10, 1000, 4_294_967_295] {
+            let square = root * root;
+            assert_eq!(root, integer_sqrt(square));
+            assert_eq!(root - 1, integer_sqrt(square - 1));
+            assert_eq!(root, integer_sqrt(square + 1));
+        }
+        assert_eq!(4_294_967_295, integer_sqrt(u64::MAX));
+        for n in 0..10_000 {
+            let root = integer_sqrt(n);
+            assert!(root * root <= n && (root + 1) * (root + 1) > n, "{n}");
+        }
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        assert_eq!(16, extrapolate(&[0, 1, 4, 9], 1));
+        assert_eq!(25, extrapolate(&[0, 1, 4, 9], 2));
+        assert_eq!(1, extrapolate(&[0, 1, 4, 9], -4));
+        assert_eq!(9, extrapolate(&[0, 1, 4, 9], 0));
+        assert_eq!(7, extrapolate(&[7], 5));
+        assert_eq!(-3, extrapolate(&[5, 1], 1));
+    }
+
+    #[test]
+    fn test_gcd_signed() {
+        assert_eq!(4, gcd_signed(-12, 8));
+        assert_eq!(4, gcd_signed(12, -8));
+        assert_eq!(4, gcd_signed(-12, -8));
+        assert_eq!(5, gcd_signed(0, -5));
+        assert_eq!(0, gcd_signed(0, 0));
+        assert_eq!(1 << 62, gcd_signed(i64::MIN, 1 << 62));
+    }
 }
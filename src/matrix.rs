@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::geometry::Point2;
 use std::hash::{Hash, Hasher};
 
 #[derive(Clone, Debug)]
@@ -34,6 +35,38 @@ where
     }
 }
 
+impl<T: Hash> Matrix<T> {
+    // Unlike hashing with `DefaultHasher`, this is deterministic across runs.
+    #[must_use]
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+// 64-bit FNV-1a.
+struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
 impl<T: Copy> Matrix<T> {
     pub fn new(width: usize, height: usize, default: T) -> Matrix<T> {
         Matrix {
@@ -43,6 +76,17 @@ impl<T: Copy> Matrix<T> {
         }
     }
 
+    // `data` must be in row-major order.
+    #[must_use]
+    pub fn from_vec(width: usize, height: usize, data: Vec<T>) -> Matrix<T> {
+        assert_eq!(width * height, data.len());
+        Matrix {
+            data,
+            width,
+            height,
+        }
+    }
+
     #[must_use]
     pub fn width(&self) -> usize {
         self.width
@@ -58,6 +102,17 @@ impl<T: Copy> Matrix<T> {
         self.data[x + y * self.width]
     }
 
+    // Unlike `get()`, returns None if `p` is out of bounds.
+    #[must_use]
+    pub fn get_point(&self, p: Point2) -> Option<T> {
+        let (x, y) = (usize::try_from(p.x).ok()?, usize::try_from(p.y).ok()?);
+        if x < self.width && y < self.height {
+            Some(self.get(x, y))
+        } else {
+            None
+        }
+    }
+
     pub fn set(&mut self, x: usize, y: usize, v: T) {
         self.data[x + y * self.width] = v;
     }
@@ -67,7 +122,7 @@ impl<T: Copy> Matrix<T> {
     }
 
     #[must_use]
-    pub fn col(&self, x: usize) -> Col<T> {
+    pub fn col(&self, x: usize) -> Col<'_, T> {
         Col {
             matrix: self,
             x,
@@ -77,7 +132,7 @@ impl<T: Copy> Matrix<T> {
     }
 
     #[must_use]
-    pub fn row(&self, y: usize) -> Row<T> {
+    pub fn row(&self, y: usize) -> Row<'_, T> {
         Row {
             matrix: self,
             x_low: 0,
@@ -168,3 +223,38 @@ impl<'a, T> DoubleEndedIterator for Row<'a, T> {
 }
 
 impl<'a, T> ExactSizeIterator for Row<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_point() {
+        let mut m = Matrix::new(3, 2, 0);
+        m.set(2, 1, 5);
+        assert_eq!(Some(5), m.get_point(Point2::new(2, 1)));
+        assert_eq!(Some(0), m.get_point(Point2::new(0, 0)));
+        assert_eq!(None, m.get_point(Point2::new(3, 1)));
+        assert_eq!(None, m.get_point(Point2::new(2, 2)));
+        assert_eq!(None, m.get_point(Point2::new(-1, 0)));
+        assert_eq!(None, m.get_point(Point2::new(0, -1)));
+    }
+
+    #[test]
+    fn content_hash() {
+        let mut a = Matrix::new(3, 2, 0u8);
+        a.set(1, 1, 7);
+        let mut b = Matrix::new(3, 2, 0u8);
+        b.set(1, 1, 7);
+        assert_eq!(a.content_hash(), b.content_hash());
+        b.set(0, 0, 1);
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn fnv1a() {
+        let mut hasher = FnvHasher::default();
+        hasher.write(b"a");
+        assert_eq!(0xaf63_dc4c_8601_ec8c, hasher.finish());
+    }
+}
@@ -18,18 +18,49 @@ use std::fmt::{Display, Formatter};
 pub enum Oops {
     Message(String),
     RealError(Box<dyn std::error::Error>),
+    // Another error with a description of where it happened, e.g. "line 3, column 7".
+    Context(String, Box<Oops>),
 }
 
 impl Display for Oops {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
-            Oops::Message(s) => write!(f, "oops: {s}")?,
+            Oops::Message(_) | Oops::Context(..) => {
+                write!(f, "oops: ")?;
+                self.fmt_unprefixed(f)?;
+            }
             Oops::RealError(e) => e.fmt(f)?,
         }
         Ok(())
     }
 }
 
+impl Oops {
+    // Prefixes the error with a 1-based position in the input, e.g. "line 3, column 7: ...".
+    #[must_use]
+    pub fn at(self, line: usize, col: usize) -> Oops {
+        self.context(format!("line {line}, column {col}"))
+    }
+
+    // Prefixes the error with a description of where it happened, keeping the original error.
+    #[must_use]
+    pub fn context(self, context: impl Into<String>) -> Oops {
+        Oops::Context(context.into(), Box::new(self))
+    }
+
+    // Formats the error without the leading "oops: ", so nested errors only print it once.
+    fn fmt_unprefixed(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Oops::Message(s) => write!(f, "{s}"),
+            Oops::RealError(e) => e.fmt(f),
+            Oops::Context(context, inner) => {
+                write!(f, "{context}: ")?;
+                inner.fmt_unprefixed(f)
+            }
+        }
+    }
+}
+
 impl<E> From<E> for Oops
 where
     E: std::error::Error + 'static,
@@ -45,3 +76,35 @@ macro_rules! oops {
         Oops::Message(format!($($e,)*))
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at() {
+        assert_eq!(
+            "oops: line 3, column 7: bad",
+            oops!("bad").at(3, 7).to_string()
+        );
+        let parse_error = "x".parse::<u32>().map_err(|e| Oops::from(e).at(1, 2));
+        let parse_error = parse_error.unwrap_err();
+        assert_eq!(
+            "oops: line 1, column 2: invalid digit found in string",
+            parse_error.to_string()
+        );
+        // The underlying error is kept rather than flattened into a string.
+        assert!(matches!(
+            parse_error,
+            Oops::Context(_, inner) if matches!(*inner, Oops::RealError(_))
+        ));
+    }
+
+    #[test]
+    fn nested_context() {
+        assert_eq!(
+            "oops: \"Game 1\": line 2, column 5: bad",
+            oops!("bad").at(2, 5).context("\"Game 1\"").to_string()
+        );
+    }
+}
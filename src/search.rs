@@ -0,0 +1,72 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::geometry::Point2;
+use std::collections::{HashMap, VecDeque};
+
+// Breadth-first search from `start`, returning every reachable point and the number of steps to
+// reach it. `neighbors` decides which points can be stepped to from a given point, so it's up to
+// the caller to keep the search bounded.
+pub fn bfs<N, I>(start: Point2, neighbors: N) -> HashMap<Point2, u32>
+where
+    N: Fn(Point2) -> I,
+    I: IntoIterator<Item = Point2>,
+{
+    let mut distances = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        let distance = distances[&current];
+        for next in neighbors(current) {
+            distances.entry(next).or_insert_with(|| {
+                queue.push_back(next);
+                distance + 1
+            });
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_grid() {
+        let in_bounds = |p: &Point2| (0..5).contains(&p.x) && (0..4).contains(&p.y);
+        let start = Point2::new(1, 2);
+        let distances = bfs(start, |p| {
+            p.cardinal_neighbors().filter(in_bounds).collect::<Vec<_>>()
+        });
+        assert_eq!(20, distances.len());
+        for (p, distance) in distances {
+            assert_eq!(
+                (p.x - start.x).unsigned_abs() + (p.y - start.y).unsigned_abs(),
+                distance
+            );
+        }
+    }
+
+    #[test]
+    fn walls() {
+        // Only the cells in row 0 and column 2 are open, so the rest of the 3x3 grid is unreachable.
+        let open =
+            |p: &Point2| (0..3).contains(&p.x) && (0..3).contains(&p.y) && (p.y == 0 || p.x == 2);
+        let distances = bfs(Point2::new(0, 0), |p| {
+            p.cardinal_neighbors().filter(open).collect::<Vec<_>>()
+        });
+        assert_eq!(5, distances.len());
+        assert_eq!(Some(&4), distances.get(&Point2::new(2, 2)));
+        assert_eq!(None, distances.get(&Point2::new(0, 2)));
+    }
+}
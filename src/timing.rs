@@ -12,8 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::io_util::{read_input, selected_parts};
+use crate::oops::Oops;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+// Evaluates the expression and prints how long it took, e.g. `time!("parse", parse(&input)?)`
+// prints "parse: 1.2ms". Without a label, the expression's source text is printed instead.
 #[macro_export]
 macro_rules! time {
+    ($label:literal, $e:expr) => {{
+        let now = std::time::Instant::now();
+        let result = $e;
+        println!("{}: {:?}", $label, now.elapsed());
+        result
+    }};
     ($e:expr) => {{
         let now = std::time::Instant::now();
         let result = $e;
@@ -21,3 +35,242 @@ macro_rules! time {
         result
     }};
 }
+
+// Like `time!`, but returns `(value, elapsed)` instead of printing the elapsed time.
+#[macro_export]
+macro_rules! time_with {
+    ($e:expr) => {{
+        let now = std::time::Instant::now();
+        let result = $e;
+        (result, now.elapsed())
+    }};
+}
+
+// Like `time!`, but adds the elapsed time to `registry` under `label` instead of printing it.
+#[macro_export]
+macro_rules! time_into {
+    ($registry:expr, $label:expr, $e:expr) => {{
+        let now = std::time::Instant::now();
+        let result = $e;
+        $registry.record($label, now.elapsed());
+        result
+    }};
+}
+
+// Accumulates durations per label across many measurements.
+#[derive(Debug, Default)]
+pub struct Registry {
+    totals: HashMap<&'static str, (Duration, usize)>,
+}
+
+impl Registry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, label: &'static str, elapsed: Duration) {
+        let (total, count) = self.totals.entry(label).or_default();
+        *total += elapsed;
+        *count += 1;
+    }
+
+    // One line per label, e.g. "solve: 1.5ms over 3 calls", with the largest total first.
+    #[must_use]
+    pub fn report(&self) -> String {
+        let mut totals = self.totals.iter().collect::<Vec<_>>();
+        totals.sort_by(|(a_label, (a_total, _)), (b_label, (b_total, _))| {
+            b_total.cmp(a_total).then_with(|| a_label.cmp(b_label))
+        });
+        totals
+            .into_iter()
+            .map(|(label, (total, count))| {
+                format!(
+                    "{label}: {total:?} over {count} call{}\n",
+                    if *count == 1 { "" } else { "s" }
+                )
+            })
+            .collect()
+    }
+}
+
+// Records a sequence of labeled durations, each measured from the end of the previous lap.
+#[derive(Debug)]
+pub struct Stopwatch {
+    last: Instant,
+    laps: Vec<(&'static str, Duration)>,
+}
+
+impl Stopwatch {
+    #[must_use]
+    pub fn start() -> Self {
+        Stopwatch {
+            last: Instant::now(),
+            laps: vec![],
+        }
+    }
+
+    pub fn lap(&mut self, label: &'static str) -> Duration {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+        self.laps.push((label, elapsed));
+        elapsed
+    }
+
+    #[must_use]
+    pub fn laps(&self) -> &[(&'static str, Duration)] {
+        &self.laps
+    }
+
+    #[must_use]
+    pub fn total(&self) -> Duration {
+        self.laps.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+}
+
+impl Display for Stopwatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        for (label, elapsed) in &self.laps {
+            write!(f, "{label}: {elapsed:?}, ")?;
+        }
+        write!(f, "total: {:?}", self.total())
+    }
+}
+
+// The shape shared by every day's binary, so the parse and part timings can be reported together.
+pub trait Solution {
+    type Puzzle;
+    type Answer1: Display;
+    type Answer2: Display;
+
+    fn parse(input: &str) -> Result<Self::Puzzle, Oops>;
+    fn part1(puzzle: &Self::Puzzle) -> Result<Self::Answer1, Oops>;
+    fn part2(puzzle: &Self::Puzzle) -> Result<Self::Answer2, Oops>;
+}
+
+// Parses `input` and runs the selected parts, returning the formatted answers along with a
+// stopwatch holding a lap for each step that ran.
+pub fn run_timed_on<S: Solution>(
+    input: &str,
+    run_part1: bool,
+    run_part2: bool,
+) -> Result<(Vec<String>, Stopwatch), Oops> {
+    let mut stopwatch = Stopwatch::start();
+    let mut answers = vec![];
+    let puzzle = S::parse(input)?;
+    stopwatch.lap("parse");
+    if run_part1 {
+        let answer = S::part1(&puzzle)?;
+        stopwatch.lap("part1");
+        answers.push(answer.to_string());
+    }
+    if run_part2 {
+        let answer = S::part2(&puzzle)?;
+        stopwatch.lap("part2");
+        answers.push(answer.to_string());
+    }
+    Ok((answers, stopwatch))
+}
+
+// A drop-in `main()` body: prints each selected answer, then a single line of timings.
+pub fn run_timed<S: Solution>() -> Result<(), Oops> {
+    let input = read_input()?;
+    let (run_part1, run_part2) = selected_parts()?;
+    let (answers, stopwatch) = run_timed_on::<S>(&input, run_part1, run_part2)?;
+    for answer in answers {
+        println!("{answer}");
+    }
+    println!("{stopwatch}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Trivial;
+
+    impl Solution for Trivial {
+        type Puzzle = Vec<u64>;
+        type Answer1 = u64;
+        type Answer2 = usize;
+
+        fn parse(input: &str) -> Result<Self::Puzzle, Oops> {
+            Ok(input
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?)
+        }
+
+        fn part1(puzzle: &Self::Puzzle) -> Result<u64, Oops> {
+            puzzle
+                .iter()
+                .try_fold(0u64, |sum, value| sum.checked_add(*value))
+                .ok_or_else(|| crate::oops!("sum overflows"))
+        }
+
+        fn part2(puzzle: &Self::Puzzle) -> Result<usize, Oops> {
+            Ok(puzzle.len())
+        }
+    }
+
+    #[test]
+    fn time_forms() {
+        assert_eq!(6, crate::time!(1 + 2 + 3));
+        assert_eq!(vec![1, 2], crate::time!("labeled", vec![1, 2]));
+        let (value, elapsed) = crate::time_with!({
+            std::thread::sleep(Duration::from_millis(1));
+            "done"
+        });
+        assert_eq!("done", value);
+        assert!(elapsed >= Duration::from_millis(1));
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = Registry::new();
+        registry.record("parse", Duration::from_millis(2));
+        registry.record("solve", Duration::from_millis(3));
+        registry.record("solve", Duration::from_millis(4));
+        let value = crate::time_into!(registry, "other", 40 + 2);
+        assert_eq!(42, value);
+        let report = registry.report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(3, lines.len());
+        assert_eq!("solve: 7ms over 2 calls", lines[0]);
+        assert_eq!("parse: 2ms over 1 call", lines[1]);
+        assert!(lines[2].starts_with("other: "), "{report}");
+    }
+
+    #[test]
+    fn summary() {
+        let (answers, stopwatch) = run_timed_on::<Trivial>("1 2 3", true, true).unwrap();
+        assert_eq!(vec!["6", "3"], answers);
+        assert_eq!(
+            vec!["parse", "part1", "part2"],
+            stopwatch
+                .laps()
+                .iter()
+                .map(|(label, _)| *label)
+                .collect::<Vec<_>>()
+        );
+        let summary = stopwatch.to_string();
+        for label in ["parse: ", "part1: ", "part2: ", "total: "] {
+            assert!(summary.contains(label), "{summary}");
+        }
+        assert!(!summary.contains('\n'));
+    }
+
+    #[test]
+    fn selected_parts_only() {
+        let (answers, stopwatch) = run_timed_on::<Trivial>("1 2 3", false, true).unwrap();
+        assert_eq!(vec!["3"], answers);
+        assert_eq!(2, stopwatch.laps().len());
+        assert!(run_timed_on::<Trivial>("x", true, true).is_err());
+        // Errors from a part are passed on too, but only when that part runs.
+        let overflow = format!("{} 1", u64::MAX);
+        assert!(run_timed_on::<Trivial>(&overflow, true, false).is_err());
+        assert!(run_timed_on::<Trivial>(&overflow, false, true).is_ok());
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2023 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[derive(Clone, Debug)]
+pub struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+    sets: usize,
+}
+
+impl UnionFind {
+    #[must_use]
+    pub fn new(n: usize) -> Self {
+        UnionFind {
+            parents: (0..n).collect(),
+            sizes: vec![1; n],
+            sets: n,
+        }
+    }
+
+    pub fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        // Compress the path so later lookups go straight to the root.
+        let mut current = i;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        // Attach the smaller tree to the larger one to keep the trees shallow.
+        let (small, large) = if self.sizes[a] < self.sizes[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[small] = large;
+        self.sizes[large] += self.sizes[small];
+        self.sets -= 1;
+    }
+
+    pub fn same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    #[must_use]
+    pub fn count_sets(&self) -> usize {
+        self.sets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn singletons() {
+        let mut sets = UnionFind::new(3);
+        assert_eq!(3, sets.count_sets());
+        assert!(sets.same(1, 1));
+        assert!(!sets.same(0, 1));
+    }
+
+    #[test]
+    fn chained_unions() {
+        let mut sets = UnionFind::new(6);
+        sets.union(0, 1);
+        sets.union(1, 2);
+        sets.union(3, 4);
+        assert!(sets.same(0, 2));
+        assert!(sets.same(3, 4));
+        assert!(!sets.same(2, 3));
+        assert_eq!(3, sets.count_sets());
+
+        sets.union(4, 0);
+        assert!(sets.same(2, 3));
+        assert!(!sets.same(5, 0));
+        assert_eq!(2, sets.count_sets());
+    }
+
+    #[test]
+    fn redundant_union() {
+        let mut sets = UnionFind::new(4);
+        sets.union(0, 1);
+        sets.union(1, 0);
+        sets.union(0, 0);
+        assert_eq!(3, sets.count_sets());
+    }
+}